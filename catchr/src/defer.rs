@@ -0,0 +1,117 @@
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::thread;
+
+/// Runs `f`, catching any panic it raises.
+pub fn catch_unwind<T>(f: impl FnOnce() -> T) -> thread::Result<T> {
+    panic::catch_unwind(AssertUnwindSafe(f))
+}
+
+/// Awaits `future`, catching any panic it raises.
+pub async fn catch_unwind_async<F: Future>(
+    future: F,
+) -> thread::Result<F::Output> {
+    CatchUnwind {
+        future: Box::pin(future),
+    }
+    .await
+}
+
+struct CatchUnwind<F> {
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for CatchUnwind<F> {
+    type Output = thread::Result<F::Output>;
+
+    fn poll(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        let future = self.future.as_mut();
+
+        match panic::catch_unwind(AssertUnwindSafe(|| future.poll(cx))) {
+            Ok(Poll::Pending) => Poll::Pending,
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Err(payload) => Poll::Ready(Err(payload)),
+        }
+    }
+}
+
+/// Finishes a section with a `defer` block, once both the code following the
+/// block (`outcome`) and the block itself (`cleanup`) have been run.
+///
+/// A panic of the section takes precedence over a panic of the cleanup, so
+/// that the original failure is the one reported.
+pub fn resume_deferred<T, C>(
+    outcome: thread::Result<T>,
+    cleanup: thread::Result<C>,
+) -> T {
+    match (outcome, cleanup) {
+        (Ok(value), Ok(_)) => value,
+        (Ok(_), Err(payload)) => panic::resume_unwind(payload),
+        (Err(payload), Ok(_)) => panic::resume_unwind(payload),
+        (Err(payload), Err(_)) => {
+            eprintln!("catchr: a deferred block panicked after a failure");
+            panic::resume_unwind(payload)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(payload: Box<dyn std::any::Any + Send>) -> String {
+        match payload.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(payload) => *payload.downcast::<String>().unwrap(),
+        }
+    }
+
+    #[test]
+    fn returns_value_if_nothing_panics() {
+        assert_eq!(resume_deferred(catch_unwind(|| 5), catch_unwind(|| ())), 5);
+    }
+
+    #[test]
+    fn propagates_panic_of_section() {
+        let payload = catch_unwind(|| {
+            resume_deferred(
+                catch_unwind(|| panic!("section")),
+                catch_unwind(|| ()),
+            )
+        })
+        .unwrap_err();
+
+        assert_eq!(message(payload), "section");
+    }
+
+    #[test]
+    fn propagates_panic_of_cleanup() {
+        let payload = catch_unwind(|| {
+            resume_deferred(
+                catch_unwind(|| ()),
+                catch_unwind(|| panic!("cleanup")),
+            )
+        })
+        .unwrap_err();
+
+        assert_eq!(message(payload), "cleanup");
+    }
+
+    #[test]
+    fn panic_of_cleanup_does_not_hide_panic_of_section() {
+        let payload = catch_unwind(|| {
+            resume_deferred(
+                catch_unwind(|| panic!("section")),
+                catch_unwind(|| panic!("cleanup")),
+            )
+        })
+        .unwrap_err();
+
+        assert_eq!(message(payload), "section");
+    }
+}
@@ -0,0 +1,285 @@
+use std::env;
+use std::iter::Peekable;
+use std::vec;
+
+/// Decides whether a test is skipped, based on the `CATCHR_FILTER` and
+/// `CATCHR_TAGS` environment variables. `path` holds the keyword and the
+/// description of every section the test is in, e.g. `given x is 1`, and
+/// `tags` all of its tags.
+///
+/// `CATCHR_FILTER` is a path with wildcards, e.g. `given x*/when 1 is added*`,
+/// which matches the tests within the sections it points at. `CATCHR_TAGS` is
+/// an expression like `db & !slow`, with `&`, `|`, `!` and parentheses.
+///
+/// Every row of an example table is a section of its own, with its title
+/// interpolated and numbered, e.g. `case 0 adds 1 and 2`.
+///
+/// The path of a `#[runtime_sections]` test (with `runtime` set) ends at the
+/// runtime section, so a filter pointing at a section nested within it runs
+/// the whole test.
+///
+/// libtest has no way of skipping a test once it's started, so the skipped
+/// tests pass, noting that they've been skipped.
+pub fn skip(path: &[&str], tags: &[&str], runtime: bool) -> bool {
+    let filter = env::var("CATCHR_FILTER").unwrap_or_default();
+    let expr = env::var("CATCHR_TAGS").unwrap_or_default();
+
+    if matches_filter(&filter, path, runtime) && matches_tags(&expr, tags) {
+        return false;
+    }
+
+    println!("catchr: skipped {}", path.join(" / "));
+
+    true
+}
+
+fn matches_filter(filter: &str, path: &[&str], runtime: bool) -> bool {
+    if filter.trim().is_empty() {
+        return true;
+    }
+
+    let patterns: Vec<_> = filter.split('/').map(str::trim).collect();
+
+    // Only a runtime test can have the section the filter points at within
+    // it, otherwise the filter has to point at an enclosing section
+    if !runtime && patterns.len() > path.len() {
+        return false;
+    }
+
+    patterns
+        .iter()
+        .zip(path)
+        .all(|(pattern, section)| matches_wildcard(pattern, section))
+}
+
+/// Matches `s` against a pattern where `*` stands for any (possibly empty)
+/// sequence of characters.
+fn matches_wildcard(pattern: &str, s: &str) -> bool {
+    let mut parts = pattern.split('*');
+
+    // There's always at least one part, even for an empty pattern
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match s.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+
+    let parts: Vec<_> = parts.collect();
+
+    let last = match parts.split_last() {
+        Some((last, middle)) => {
+            for part in middle {
+                match rest.find(part) {
+                    Some(idx) => rest = &rest[idx + part.len()..],
+                    None => return false,
+                }
+            }
+
+            last
+        }
+        // No wildcards - the whole string has to match
+        None => return rest.is_empty(),
+    };
+
+    rest.ends_with(last)
+}
+
+fn matches_tags(expr: &str, tags: &[&str]) -> bool {
+    if expr.trim().is_empty() {
+        return true;
+    }
+
+    match TagExpr::parse(expr) {
+        Some(expr) => expr.matches(tags),
+        None => {
+            panic!("catchr: invalid tag expression in CATCHR_TAGS: {}", expr)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum TagExpr {
+    Tag(String),
+    Not(Box<TagExpr>),
+    And(Box<TagExpr>, Box<TagExpr>),
+    Or(Box<TagExpr>, Box<TagExpr>),
+}
+
+impl TagExpr {
+    fn parse(s: &str) -> Option<Self> {
+        let mut tokens = tokenize(s)?.into_iter().peekable();
+
+        let expr = Self::parse_or(&mut tokens)?;
+
+        match tokens.next() {
+            Some(_) => None,
+            None => Some(expr),
+        }
+    }
+
+    fn parse_or(tokens: &mut Tokens) -> Option<Self> {
+        let mut expr = Self::parse_and(tokens)?;
+
+        while tokens.next_if(|token| token == "|").is_some() {
+            expr = Self::Or(Box::new(expr), Box::new(Self::parse_and(tokens)?));
+        }
+
+        Some(expr)
+    }
+
+    fn parse_and(tokens: &mut Tokens) -> Option<Self> {
+        let mut expr = Self::parse_not(tokens)?;
+
+        while tokens.next_if(|token| token == "&").is_some() {
+            expr =
+                Self::And(Box::new(expr), Box::new(Self::parse_not(tokens)?));
+        }
+
+        Some(expr)
+    }
+
+    fn parse_not(tokens: &mut Tokens) -> Option<Self> {
+        match tokens.next()?.as_str() {
+            "!" => Some(Self::Not(Box::new(Self::parse_not(tokens)?))),
+            "(" => {
+                let expr = Self::parse_or(tokens)?;
+                tokens.next_if(|token| token == ")")?;
+
+                Some(expr)
+            }
+            "&" | "|" | ")" => None,
+            tag => Some(Self::Tag(tag.to_string())),
+        }
+    }
+
+    fn matches(&self, tags: &[&str]) -> bool {
+        match self {
+            Self::Tag(tag) => tags.contains(&tag.as_str()),
+            Self::Not(expr) => !expr.matches(tags),
+            Self::And(lhs, rhs) => lhs.matches(tags) && rhs.matches(tags),
+            Self::Or(lhs, rhs) => lhs.matches(tags) || rhs.matches(tags),
+        }
+    }
+}
+
+type Tokens = Peekable<vec::IntoIter<String>>;
+
+/// Splits a tag expression into operators, parentheses and tags.
+fn tokenize(s: &str) -> Option<Vec<String>> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '&' | '|' | '!' | '(' | ')' => tokens.push(ch.to_string()),
+            ch if ch.is_whitespace() => {}
+            ch if is_tag_char(ch) => {
+                let mut tag = ch.to_string();
+
+                while let Some(ch) = chars.next_if(|ch| is_tag_char(*ch)) {
+                    tag.push(ch);
+                }
+
+                tokens.push(tag);
+            }
+            _ => return None,
+        }
+    }
+
+    Some(tokens)
+}
+
+fn is_tag_char(ch: char) -> bool {
+    ch.is_alphanumeric() || matches!(ch, '_' | '-' | '.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATH: &[&str] = &[
+        "section my tests",
+        "given x is equal to 1",
+        "when 1 is added to x",
+        "then x should equal 2",
+    ];
+
+    #[test]
+    fn filters_by_path() {
+        assert!(matches_filter("", PATH, false));
+        assert!(matches_filter("section my tests", PATH, false));
+        assert!(matches_filter("*/given x*/when 1 is added*", PATH, false));
+        assert!(matches_filter(" * / * / * / then * ", PATH, false));
+        assert!(!matches_filter("*/given x*/when 2 is added*", PATH, false));
+        assert!(!matches_filter("section my", PATH, false));
+        assert!(!matches_filter("*/*/*/*/*", PATH, false));
+    }
+
+    #[test]
+    fn filters_by_example_rows() {
+        let path = &["section my tests", "case 0 adds 1 and 2"];
+
+        assert!(matches_filter("*/case 0 *", path, false));
+        assert!(matches_filter("*/* adds 1 and 2", path, false));
+        assert!(!matches_filter("*/case adds {a} and {b}", path, false));
+    }
+
+    #[test]
+    fn filters_by_paths_into_runtime_sections() {
+        let path = &["section my tests", "given x is equal to 1"];
+
+        assert!(matches_filter("*/given x*/when 1 is added*", path, true));
+        assert!(matches_filter("*/*/*/*/*", path, true));
+        assert!(!matches_filter("*/given y*/when 1 is added*", path, true));
+        assert!(!matches_filter("*/given x*/when 1 is added*", path, false));
+    }
+
+    #[test]
+    fn matches_wildcards() {
+        assert!(matches_wildcard("given x*", "given x is equal to 1"));
+        assert!(matches_wildcard("*equal*1", "given x is equal to 1"));
+        assert!(matches_wildcard("given*to*", "given x is equal to 1"));
+        assert!(matches_wildcard("*", ""));
+        assert!(!matches_wildcard("given", "given x"));
+        assert!(!matches_wildcard("*x*x*", "given x"));
+        assert!(!matches_wildcard("a*a", "a"));
+    }
+
+    #[test]
+    fn filters_by_tags() {
+        assert!(matches_tags("", &[]));
+        assert!(matches_tags("db", &["db", "slow"]));
+        assert!(matches_tags("db & !slow", &["db"]));
+        assert!(!matches_tags("db & !slow", &["db", "slow"]));
+        assert!(matches_tags("!(db | slow)", &["fast"]));
+        assert!(matches_tags("db | slow & fast", &["db"]));
+        assert!(!matches_tags("(db | slow) & fast", &["db"]));
+        assert!(matches_tags("!!db", &["db"]));
+    }
+
+    #[test]
+    fn parses_tag_exprs() {
+        let tag = |tag: &str| Box::new(TagExpr::Tag(tag.to_string()));
+
+        assert_eq!(
+            TagExpr::parse("a | b & !c"),
+            Some(TagExpr::Or(
+                tag("a"),
+                Box::new(TagExpr::And(
+                    tag("b"),
+                    Box::new(TagExpr::Not(tag("c")))
+                ))
+            ))
+        );
+        assert_eq!(TagExpr::parse("a &"), None);
+        assert_eq!(TagExpr::parse("(a"), None);
+        assert_eq!(TagExpr::parse("a b"), None);
+        assert_eq!(TagExpr::parse("a, b"), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid tag expression in CATCHR_TAGS: db &")]
+    fn panics_on_invalid_tag_exprs() {
+        matches_tags("db &", &[]);
+    }
+}
@@ -0,0 +1,210 @@
+use std::fmt::Debug;
+use std::thread;
+
+/// Keeps track of the values picked by the `generate!` statements of a
+/// test, so that the test is run once for every combination of them.
+///
+/// Generators are identified by the order in which they're reached, so a
+/// generator nested in another one is started over every time the outer one
+/// moves on to its next value.
+#[derive(Debug, Default)]
+pub struct Generators {
+    generators: Vec<Generator>,
+    current: usize,
+    started: bool,
+}
+
+#[derive(Debug)]
+struct Generator {
+    name: &'static str,
+    index: usize,
+    len: usize,
+    value: String,
+}
+
+impl Generators {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Prepares the next run of the test, returning `false` once all of the
+    /// combinations have been exhausted.
+    pub fn next_run(&mut self) -> bool {
+        // Generators that weren't reached during the last run start over
+        // whenever they're reached again
+        self.generators.truncate(self.current);
+        self.current = 0;
+
+        if !self.started {
+            self.started = true;
+            return true;
+        }
+
+        while let Some(last) = self.generators.last_mut() {
+            last.index += 1;
+
+            if last.index < last.len {
+                return true;
+            }
+
+            self.generators.pop();
+        }
+
+        false
+    }
+
+    /// Picks the value of the generator for the current run.
+    pub fn pick<T: Debug>(
+        &mut self,
+        name: &'static str,
+        values: impl IntoIterator<Item = T>,
+    ) -> T {
+        let values: Vec<T> = values.into_iter().collect();
+
+        if values.is_empty() {
+            panic!("generator `{}` didn't produce any values", name);
+        }
+
+        if self.current == self.generators.len() {
+            self.generators.push(Generator {
+                name,
+                index: 0,
+                len: 0,
+                value: String::new(),
+            });
+        }
+
+        let generator = &mut self.generators[self.current];
+        self.current += 1;
+
+        generator.len = values.len();
+        generator.index = generator.index.min(generator.len - 1);
+
+        let value = values.into_iter().nth(generator.index).unwrap();
+        generator.value = format!("{:?}", value);
+
+        value
+    }
+
+    /// The values picked for the current run, e.g. `x = 5, y = "a"`.
+    pub fn describe(&self) -> String {
+        self.generators
+            .iter()
+            .take(self.current)
+            .map(|generator| {
+                format!("{} = {}", generator.name, generator.value)
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl Drop for Generators {
+    fn drop(&mut self) {
+        if thread::panicking() && self.current > 0 {
+            eprintln!(
+                "catchr: failed for generated values: {}",
+                self.describe()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(mut test: impl FnMut(&mut Generators) -> String) -> Vec<String> {
+        let mut generators = Generators::new();
+        let mut runs = vec![];
+
+        while generators.next_run() {
+            runs.push(test(&mut generators));
+        }
+
+        runs
+    }
+
+    #[test]
+    fn without_generators_runs_once() {
+        assert_eq!(run(|_| "run".to_string()), vec!["run"]);
+    }
+
+    #[test]
+    fn runs_every_value() {
+        let runs = run(|g| g.pick("x", [1, 5, 10]).to_string());
+
+        assert_eq!(runs, vec!["1", "5", "10"]);
+    }
+
+    #[test]
+    fn runs_cartesian_product() {
+        let runs = run(|g| {
+            let x = g.pick("x", 0..2);
+            let y = g.pick("y", ["a", "b", "c"]);
+
+            format!("{}{}", x, y)
+        });
+
+        assert_eq!(runs, vec!["0a", "0b", "0c", "1a", "1b", "1c"]);
+    }
+
+    #[test]
+    fn supports_dependent_generators() {
+        let runs = run(|g| {
+            let x = g.pick("x", 1..=3);
+            let y = g.pick("y", 0..x);
+
+            format!("{}{}", x, y)
+        });
+
+        assert_eq!(runs, vec!["10", "20", "21", "30", "31", "32"]);
+    }
+
+    #[test]
+    fn supports_generators_reached_conditionally() {
+        let runs = run(|g| {
+            let x = g.pick("x", [true, false]);
+
+            if x {
+                g.pick("y", 0..2).to_string()
+            } else {
+                "none".to_string()
+            }
+        });
+
+        assert_eq!(runs, vec!["0", "1", "none"]);
+    }
+
+    #[test]
+    fn restarts_generators_not_reached_in_last_run() {
+        let runs = run(|g| {
+            let x = g.pick("x", [false, true]);
+
+            if x {
+                g.pick("y", 0..2).to_string()
+            } else {
+                "none".to_string()
+            }
+        });
+
+        assert_eq!(runs, vec!["none", "0", "1"]);
+    }
+
+    #[test]
+    fn describes_picked_values() {
+        let mut generators = Generators::new();
+        generators.next_run();
+
+        generators.pick("x", [1, 2]);
+        generators.pick("name", ["bob"]);
+
+        assert_eq!(generators.describe(), r#"x = 1, name = "bob""#);
+    }
+
+    #[test]
+    #[should_panic(expected = "generator `x` didn't produce any values")]
+    fn panics_on_empty_generator() {
+        run(|g| g.pick("x", Vec::<u32>::new()).to_string());
+    }
+}
@@ -1,24 +1,23 @@
-extern crate proc_macro;
+mod defer;
+mod filter;
+mod generators;
+mod timeout;
+mod tracker;
 
-use catchr_core::{CatchrMode, Section};
-use proc_macro::TokenStream;
-use quote::ToTokens;
-use syn::parse_macro_input;
+pub use catchr_macros::{
+    describe, describe_async, describe_async_std, describe_smol,
+    describe_tokio, describe_with, spec,
+};
 
-#[proc_macro]
-pub fn describe(input: TokenStream) -> TokenStream {
-    let section_body = parse_macro_input!(input as Section);
+/// Support code for the tests generated by the macros - not a public API.
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "proptest")]
+    pub use proptest;
 
-    let output = section_body.to_token_stream();
-
-    output.into()
-}
-
-#[proc_macro]
-pub fn describe_tokio(input: TokenStream) -> TokenStream {
-    let section_body = parse_macro_input!(input as Section);
-
-    let output = section_body.with_mode(CatchrMode::Tokio).to_token_stream();
-
-    output.into()
+    pub use crate::defer::{catch_unwind, catch_unwind_async, resume_deferred};
+    pub use crate::filter::skip;
+    pub use crate::generators::Generators;
+    pub use crate::timeout::run_with_timeout;
+    pub use crate::tracker::Tracker;
 }
@@ -0,0 +1,65 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+/// Runs a test on a separate thread, panicking with `message` if it doesn't
+/// finish within `limit`.
+///
+/// A test that timed out can't be stopped, so its thread is left running in
+/// the background.
+pub fn run_with_timeout<T: Send + 'static>(
+    limit: Duration,
+    message: &str,
+    test: impl FnOnce() -> T + Send + 'static,
+) -> T {
+    let (tx, rx) = mpsc::channel();
+
+    let mut builder = thread::Builder::new();
+
+    if let Some(name) = thread::current().name() {
+        builder = builder.name(name.to_string());
+    }
+
+    builder
+        .spawn(move || {
+            let outcome = panic::catch_unwind(AssertUnwindSafe(test));
+            let _ = tx.send(outcome);
+        })
+        .expect("failed to spawn the thread of a test");
+
+    match rx.recv_timeout(limit) {
+        Ok(Ok(output)) => output,
+        Ok(Err(payload)) => panic::resume_unwind(payload),
+        Err(RecvTimeoutError::Timeout) => panic!("{}", message),
+        Err(RecvTimeoutError::Disconnected) => {
+            unreachable!("the thread of a test exited without a result")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn returns_output() {
+        assert_eq!(run_with_timeout(Duration::from_secs(10), "", || 5), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "boom")]
+    fn propagates_panics() {
+        run_with_timeout(Duration::from_secs(10), "", || panic!("boom"));
+    }
+
+    #[test]
+    #[should_panic(expected = "then \"x\" timed out after 10ms")]
+    fn panics_on_timeout() {
+        run_with_timeout(
+            Duration::from_millis(10),
+            "then \"x\" timed out after 10ms",
+            || thread::sleep(Duration::from_secs(1)),
+        );
+    }
+}
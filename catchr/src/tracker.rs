@@ -0,0 +1,421 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::thread;
+
+/// Decides which sections are entered on every run of a test with runtime
+/// sections, so that each run goes down a different path.
+///
+/// On every run the first section (at every level) that hasn't been
+/// completed yet is entered. Once a section is left, its remaining siblings
+/// are skipped until the next run. A section is completed when it has been
+/// left without skipping any of its nested sections, or when it failed.
+///
+/// A run interrupted within a section - by a failure, or by returning early -
+/// completes it, as its nested sections would be skipped the same way. Its
+/// ancestors are completed right away too if nothing is left in them, so
+/// that no run is wasted on their setup alone.
+///
+/// Sibling sections with the same title (e.g. dynamic sections generated for
+/// fixtures sharing a name) are told apart by the order they're seen in on
+/// every run, the second one being called `title (#2)` and so on.
+pub struct Tracker {
+    root: &'static str,
+    state: RefCell<State>,
+}
+
+#[derive(Default)]
+struct State {
+    completed: HashSet<Vec<String>>,
+    /// The titles of the sections seen within every section so far.
+    children: HashMap<Vec<String>, Vec<String>>,
+    /// The sections that have been run to their end at least once.
+    explored: HashSet<Vec<String>>,
+    stack: Vec<Frame>,
+    left: bool,
+    done: bool,
+    failures: Vec<String>,
+}
+
+#[derive(Default)]
+struct Frame {
+    title: String,
+    pending: bool,
+    /// How many times each title was seen within this section on this run.
+    seen: HashMap<String, usize>,
+}
+
+impl State {
+    fn path(&self) -> Vec<String> {
+        self.stack
+            .iter()
+            .skip(1)
+            .map(|frame| frame.title.clone())
+            .collect()
+    }
+
+    /// Completes the section a run was interrupted in, and then its
+    /// ancestors, as long as nothing is left in them.
+    fn complete_interrupted(&mut self, mut path: Vec<String>) {
+        loop {
+            if path.is_empty() {
+                self.done = true;
+                return;
+            }
+
+            self.completed.insert(path.clone());
+            path.pop();
+
+            if !self.is_exhausted(&path) {
+                return;
+            }
+        }
+    }
+
+    /// Whether a section has been run to its end, with all of the sections
+    /// within it completed since.
+    fn is_exhausted(&self, path: &[String]) -> bool {
+        let mut children = self.children.get(path).into_iter().flatten();
+
+        self.explored.contains(path)
+            && children.all(|child| {
+                let mut child_path = path.to_vec();
+                child_path.push(child.clone());
+
+                self.completed.contains(&child_path)
+            })
+    }
+}
+
+impl Tracker {
+    /// `root` is the path of the test, to which the paths of the sections
+    /// are appended in failure messages.
+    pub fn new(root: &'static str) -> Self {
+        Self {
+            root,
+            state: RefCell::default(),
+        }
+    }
+
+    /// Starts the next run, returning `false` once all the sections have
+    /// been completed.
+    pub fn start_pass(&self) -> bool {
+        let mut state = self.state.borrow_mut();
+
+        state.stack = vec![Frame::default()];
+        state.left = false;
+
+        !state.done
+    }
+
+    /// Decides whether the section with the given title is entered on this
+    /// run. If it is, it has to be followed by a call to `leave`.
+    pub fn enter(&self, title: &str) -> bool {
+        let mut state = self.state.borrow_mut();
+
+        let parent = state.stack.last_mut().expect("entered outside of a run");
+        let seen = parent.seen.entry(title.to_string()).or_default();
+        *seen += 1;
+
+        let title = match *seen {
+            1 => title.to_string(),
+            seen => format!("{} (#{})", title, seen),
+        };
+        let title = title.as_str();
+
+        let mut path = state.path();
+
+        let children = state.children.entry(path.clone()).or_default();
+
+        if !children.iter().any(|child| child == title) {
+            children.push(title.to_string());
+        }
+
+        path.push(title.to_string());
+
+        if state.completed.contains(&path) {
+            return false;
+        }
+
+        if state.left {
+            if let Some(parent) = state.stack.last_mut() {
+                parent.pending = true;
+            }
+
+            return false;
+        }
+
+        state.stack.push(Frame {
+            title: title.to_string(),
+            ..Frame::default()
+        });
+
+        true
+    }
+
+    pub fn leave(&self) {
+        let mut state = self.state.borrow_mut();
+
+        let path = state.path();
+        let frame = state.stack.pop().expect("left a section never entered");
+
+        state.explored.insert(path.clone());
+
+        if frame.pending {
+            if let Some(parent) = state.stack.last_mut() {
+                parent.pending = true;
+            }
+        } else {
+            state.completed.insert(path);
+        }
+
+        state.left = true;
+    }
+
+    /// Finishes a run, recording its failure (if any) together with the path
+    /// of the section it happened in.
+    pub fn end_pass<E: Debug>(&self, outcome: thread::Result<Result<(), E>>) {
+        let mut state = self.state.borrow_mut();
+        let path = state.path();
+
+        match outcome {
+            // Returned early, from within a section
+            Ok(Ok(())) if !path.is_empty() => {
+                state.complete_interrupted(path);
+                return;
+            }
+            Ok(Ok(())) => {
+                state.explored.insert(path);
+                state.done = !state.stack[0].pending;
+                return;
+            }
+            _ => {}
+        }
+
+        let title = self.title(&path);
+
+        match outcome {
+            Ok(Err(err)) => eprintln!("catchr: {} failed: {:?}", title, err),
+            _ => eprintln!("catchr: {} panicked", title),
+        }
+
+        state.failures.push(title);
+        state.complete_interrupted(path);
+    }
+
+    /// Fails the test if any of the runs failed, listing their paths.
+    pub fn finish(&self) {
+        let state = self.state.borrow();
+
+        if state.failures.is_empty() {
+            return;
+        }
+
+        let paths = match state.failures.len() {
+            1 => "path",
+            _ => "paths",
+        };
+
+        panic!(
+            "catchr: {} {} failed:\n    {}",
+            state.failures.len(),
+            paths,
+            state.failures.join("\n    "),
+        );
+    }
+
+    fn title(&self, path: &[String]) -> String {
+        let mut title = vec![self.root.to_string()];
+        title.extend_from_slice(path);
+
+        title.join(" / ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use super::*;
+    use crate::defer::catch_unwind;
+
+    fn section(
+        tracker: &Tracker,
+        title: &'static str,
+        entered: &mut Vec<&'static str>,
+        nested: impl FnOnce(&mut Vec<&'static str>),
+    ) {
+        if tracker.enter(title) {
+            entered.push(title);
+            nested(entered);
+            tracker.leave();
+        }
+    }
+
+    /// Runs a test shaped like
+    ///
+    /// ```text
+    /// section "root" {
+    ///     when "a" {
+    ///         then "b" {}
+    ///         then "c" {}
+    ///     }
+    ///     when "d" {}
+    /// }
+    /// ```
+    ///
+    /// returning the sections entered on every run. The section called
+    /// `fail` panics.
+    fn run(tracker: &Tracker, fail: &str) -> Vec<Vec<&'static str>> {
+        let mut runs = vec![];
+
+        while tracker.start_pass() {
+            let mut entered = vec![];
+
+            let outcome = catch_unwind(|| {
+                section(tracker, "a", &mut entered, |entered| {
+                    assert_ne!(fail, "a");
+                    section(tracker, "b", entered, |_| assert_ne!(fail, "b"));
+                    section(tracker, "c", entered, |_| assert_ne!(fail, "c"));
+                });
+                section(tracker, "d", &mut entered, |_| assert_ne!(fail, "d"));
+
+                Ok::<(), Infallible>(())
+            });
+
+            tracker.end_pass(outcome);
+            runs.push(entered);
+        }
+
+        runs
+    }
+
+    #[test]
+    fn runs_every_path() {
+        let tracker = Tracker::new("section \"root\"");
+
+        assert_eq!(
+            run(&tracker, ""),
+            vec![vec!["a", "b"], vec!["a", "c"], vec!["d"]]
+        );
+        tracker.finish();
+    }
+
+    #[test]
+    fn continues_after_failures() {
+        let tracker = Tracker::new("section \"root\"");
+
+        assert_eq!(
+            run(&tracker, "b"),
+            vec![vec!["a", "b"], vec!["a", "c"], vec!["d"]]
+        );
+        assert_eq!(
+            tracker.state.borrow().failures,
+            vec!["section \"root\" / a / b"]
+        );
+    }
+
+    #[test]
+    fn skips_nested_sections_of_failures() {
+        let tracker = Tracker::new("section \"root\"");
+
+        assert_eq!(run(&tracker, "a"), vec![vec!["a"], vec!["d"]]);
+        assert_eq!(
+            tracker.state.borrow().failures,
+            vec!["section \"root\" / a"]
+        );
+    }
+
+    #[test]
+    fn completes_ancestors_of_failures() {
+        let tracker = Tracker::new("section \"root\"");
+
+        assert_eq!(
+            run(&tracker, "c"),
+            vec![vec!["a", "b"], vec!["a", "c"], vec!["d"]]
+        );
+
+        let tracker = Tracker::new("section \"root\"");
+
+        assert_eq!(
+            run(&tracker, "d"),
+            vec![vec!["a", "b"], vec!["a", "c"], vec!["d"]]
+        );
+    }
+
+    #[test]
+    fn tells_apart_sections_with_the_same_title() {
+        let tracker = Tracker::new("section \"root\"");
+        let mut runs = vec![];
+
+        while tracker.start_pass() {
+            let mut entered = vec![];
+
+            let outcome = catch_unwind(|| {
+                for (idx, id) in [1, 2, 1, 3].into_iter().enumerate() {
+                    if tracker.enter(&format!("user {}", id)) {
+                        entered.push(idx);
+                        assert_ne!(idx, 2);
+                        tracker.leave();
+                    }
+                }
+
+                Ok::<(), Infallible>(())
+            });
+
+            tracker.end_pass(outcome);
+            runs.push(entered);
+        }
+
+        assert_eq!(runs, vec![vec![0], vec![1], vec![2], vec![3]]);
+        assert_eq!(
+            tracker.state.borrow().failures,
+            vec!["section \"root\" / user 1 (#2)"]
+        );
+    }
+
+    #[test]
+    fn continues_after_early_returns() {
+        let tracker = Tracker::new("section \"root\"");
+        let mut runs = vec![];
+
+        while tracker.start_pass() {
+            let mut entered = vec![];
+
+            let outcome = catch_unwind(|| {
+                if tracker.enter("a") {
+                    entered.push("a");
+
+                    if tracker.enter("b") {
+                        entered.push("b");
+                        return Ok(());
+                    }
+
+                    section(&tracker, "c", &mut entered, |_| {});
+                    tracker.leave();
+                }
+
+                section(&tracker, "d", &mut entered, |_| {});
+
+                Ok::<(), Infallible>(())
+            });
+
+            tracker.end_pass(outcome);
+            runs.push(entered);
+        }
+
+        assert_eq!(runs, vec![vec!["a", "b"], vec!["a", "c"], vec!["d"]]);
+        tracker.finish();
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "catchr: 1 path failed:\n    section \"root\" / d"
+    )]
+    fn finish_reports_failures() {
+        let tracker = Tracker::new("section \"root\"");
+
+        run(&tracker, "d");
+        tracker.finish();
+    }
+}
@@ -1,5 +1,153 @@
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use syn::parse;
+
+use crate::scope::Scope;
+use crate::timeout::Timeout;
+use crate::tokio_args::TokioArgs;
+
+/// Decides how the tests are run - synchronously, or on one of the supported
+/// async runtimes.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CatchrMode {
     Regular,
-    Tokio,
+    Tokio(TokioArgs),
+    AsyncStd,
+    Smol,
+    /// Runs every test with the given executor, e.g.
+    /// `futures::executor::block_on`.
+    Async(syn::Path),
+    /// Marks every test with the given attributes (e.g. `#[sqlx::test]`),
+    /// instead of `#[test]`.
+    Custom {
+        attrs: Vec<syn::Attribute>,
+        is_async: bool,
+    },
+}
+
+impl CatchrMode {
+    pub fn is_async(&self) -> bool {
+        match self {
+            Self::Regular => false,
+            Self::Custom { is_async, .. } => *is_async,
+            _ => true,
+        }
+    }
+
+    /// The mode of the tests in an `async` section. Plain tests become tokio
+    /// ones, while modes that are async already are kept as they are.
+    pub fn to_async(&self) -> Self {
+        match self {
+            Self::Regular => Self::Tokio(TokioArgs::default()),
+            Self::Custom { attrs, .. } => Self::Custom {
+                attrs: attrs.clone(),
+                is_async: true,
+            },
+            mode => mode.clone(),
+        }
+    }
+
+    /// Quotes a test function. `output` is its return type (if any) and
+    /// `inner` its body, run unless `skip` returns first - before a runtime
+    /// or a thread for the timeout is started.
+    pub fn quote_test(
+        &self,
+        name: &Ident,
+        attrs: &[syn::Attribute],
+        output: TokenStream,
+        skip: TokenStream,
+        inner: TokenStream,
+        scope: &Scope,
+    ) -> TokenStream {
+        let body = match self.quote_body(inner, scope.timeout(), &scope.path())
+        {
+            Ok(body) => quote!(#skip #body),
+            Err(err) => return err.to_compile_error(),
+        };
+
+        match self {
+            Self::Regular | Self::Smol | Self::Async(_) => quote! {
+                #[test]
+                #(#attrs)*
+                fn #name() #output {
+                    #body
+                }
+            },
+            Self::Tokio(args) => {
+                let attr = args.quote_attr();
+
+                quote! {
+                    #attr
+                    #(#attrs)*
+                    async fn #name() #output {
+                        #body
+                    }
+                }
+            }
+            Self::AsyncStd => quote! {
+                #[async_std::test]
+                #(#attrs)*
+                async fn #name() #output {
+                    #body
+                }
+            },
+            Self::Custom {
+                attrs: test_attrs,
+                is_async,
+            } => {
+                let asyncness = if *is_async {
+                    quote!(async)
+                } else {
+                    TokenStream::new()
+                };
+
+                quote! {
+                    #(#test_attrs)*
+                    #(#attrs)*
+                    #asyncness fn #name() #output {
+                        #body
+                    }
+                }
+            }
+        }
+    }
+
+    /// The body of a test function, failing the test if it doesn't finish
+    /// within the timeout. Tests that are async only because of the executor
+    /// are sync functions, so they're limited like regular ones.
+    fn quote_body(
+        &self,
+        inner: TokenStream,
+        timeout: Option<&Timeout>,
+        path: &str,
+    ) -> parse::Result<TokenStream> {
+        let run = match self {
+            Self::Smol => quote!(::smol::block_on(async { #inner })),
+            Self::Async(executor) => quote!(#executor(async { #inner })),
+            _ => inner,
+        };
+
+        let timeout = match timeout {
+            Some(timeout) => timeout,
+            None => return Ok(run),
+        };
+
+        match self {
+            Self::Tokio(_) => Ok(timeout.quote_future(
+                quote!(::tokio::time::timeout),
+                run,
+                path,
+            )),
+            Self::AsyncStd => Ok(timeout.quote_future(
+                quote!(::async_std::future::timeout),
+                run,
+                path,
+            )),
+            Self::Custom { is_async: true, .. } => Err(parse::Error::new(
+                timeout.span(),
+                "timeouts aren't supported for async tests with custom attributes",
+            )),
+            _ => Ok(timeout.quote_watchdog(run, path)),
+        }
+    }
 }
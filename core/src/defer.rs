@@ -0,0 +1,127 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::{self, Parse, ParseStream};
+
+use crate::catchr_mode::CatchrMode;
+use crate::returns::Returns;
+
+mod kw {
+    syn::custom_keyword!(defer);
+}
+
+/// A block of cleanup code, run at the end of the enclosing section, e.g.
+///
+/// ```text
+/// given "a temporary directory" {
+///     let dir = create_temp_dir();
+///     defer {
+///         fs::remove_dir_all(&dir).unwrap();
+///     }
+///     ...
+/// }
+/// ```
+///
+/// The block runs even if the code following it panics, in which case the
+/// original panic is propagated once the block is done.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Defer {
+    block: syn::Block,
+}
+
+impl Defer {
+    pub fn new(block: syn::Block) -> Self {
+        Self { block }
+    }
+
+    pub fn peek(input: ParseStream) -> bool {
+        input.peek(kw::defer) && input.peek2(syn::token::Brace)
+    }
+
+    pub fn block(&self) -> &syn::Block {
+        &self.block
+    }
+
+    /// Runs the statements following the `defer` (`rest`) and then the
+    /// deferred block, both with panics caught. Errors returned with `?` from
+    /// `rest` are passed on once the deferred block is done.
+    pub fn quote_around(
+        &self,
+        rest: TokenStream,
+        mode: &CatchrMode,
+        returns: Option<&Returns>,
+    ) -> TokenStream {
+        let block = &self.block;
+
+        let (outcome, cleanup) = match (mode.is_async(), returns) {
+            (false, None) => (
+                quote!(::catchr::__private::catch_unwind(|| { #rest })),
+                quote!(::catchr::__private::catch_unwind(|| #block)),
+            ),
+            (false, Some(returns)) => {
+                let ty = returns.ty();
+
+                (
+                    quote!(::catchr::__private::catch_unwind(|| -> #ty {
+                        #rest
+
+                        ::std::result::Result::Ok(())
+                    })),
+                    quote!(::catchr::__private::catch_unwind(|| #block)),
+                )
+            }
+            (true, None) => (
+                quote!(
+                    ::catchr::__private::catch_unwind_async(async { #rest })
+                        .await
+                ),
+                quote!(
+                    ::catchr::__private::catch_unwind_async(async #block)
+                        .await
+                ),
+            ),
+            (true, Some(returns)) => {
+                let ty = returns.ty();
+
+                (
+                    quote!(
+                        ::catchr::__private::catch_unwind_async(async {
+                            #rest
+
+                            ::std::convert::identity::<#ty>(
+                                ::std::result::Result::Ok(())
+                            )
+                        })
+                        .await
+                    ),
+                    quote!(
+                        ::catchr::__private::catch_unwind_async(async #block)
+                            .await
+                    ),
+                )
+            }
+        };
+
+        let propagate = if returns.is_some() {
+            quote!(?)
+        } else {
+            TokenStream::new()
+        };
+
+        quote! {
+            let __catchr_outcome = #outcome;
+            let __catchr_cleanup = #cleanup;
+            ::catchr::__private::resume_deferred(
+                __catchr_outcome,
+                __catchr_cleanup,
+            )#propagate;
+        }
+    }
+}
+
+impl Parse for Defer {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        input.parse::<kw::defer>()?;
+
+        Ok(Self::new(input.parse()?))
+    }
+}
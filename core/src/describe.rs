@@ -0,0 +1,379 @@
+use proc_macro2::TokenStream;
+use quote::{ToTokens, TokenStreamExt};
+use syn::parse::{self, Parse, ParseStream};
+
+use crate::catchr_mode::CatchrMode;
+use crate::focus;
+use crate::scope::Scope;
+use crate::section::Section;
+use crate::sibling_names::SiblingNames;
+use crate::tag_rule::TagRule;
+use crate::test_limit::TestLimit;
+
+/// The input of a `describe!` block - a sequence of root sections,
+/// interleaved with regular items (`use` declarations, helper functions
+/// and so on) that are visible to all of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Describe {
+    items: Vec<syn::Item>,
+    sections: Vec<Section>,
+    limit: TestLimit,
+    tag_rules: Vec<TagRule>,
+    pending_if_empty: bool,
+}
+
+impl Describe {
+    pub fn new(items: Vec<syn::Item>, sections: Vec<Section>) -> Self {
+        Self {
+            items,
+            sections,
+            limit: TestLimit::default(),
+            tag_rules: vec![],
+            pending_if_empty: false,
+        }
+    }
+
+    pub fn with_mode(mut self, test_attribute: CatchrMode) -> Self {
+        self.sections = self
+            .sections
+            .into_iter()
+            .map(|section| section.with_mode(test_attribute.clone()))
+            .collect();
+
+        self
+    }
+
+    pub fn items(&self) -> &[syn::Item] {
+        &self.items
+    }
+
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    pub fn with_limit(mut self, limit: TestLimit) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn limit(&self) -> &TestLimit {
+        &self.limit
+    }
+
+    pub fn with_tag_rules(mut self, tag_rules: Vec<TagRule>) -> Self {
+        self.tag_rules = tag_rules;
+        self
+    }
+
+    pub fn tag_rules(&self) -> &[TagRule] {
+        &self.tag_rules
+    }
+
+    /// Treats the sections with empty bodies as pending, like `xthen`, so
+    /// that tests can be sketched out before they're written.
+    pub fn with_pending_if_empty(mut self, pending_if_empty: bool) -> Self {
+        self.pending_if_empty = pending_if_empty;
+        self
+    }
+
+    pub fn pending_if_empty(&self) -> bool {
+        self.pending_if_empty
+    }
+
+    /// Checks for the attributes configuring the whole block, which are
+    /// written at its top, e.g. `#![max_tests = 500]`.
+    pub fn is_config_attr(attr: &syn::Attribute) -> bool {
+        TestLimit::is_limit_attr(attr)
+            || TagRule::is_tag_attr(attr)
+            || attr.path.is_ident("pending_if_empty")
+    }
+
+    /// Applies the attributes at the top of the block. If the limit is set
+    /// multiple times, the last one wins.
+    fn configure(&mut self, attrs: &[syn::Attribute]) -> parse::Result<()> {
+        for attr in attrs {
+            if TestLimit::is_limit_attr(attr) {
+                self.limit = TestLimit::from_attr(attr)?;
+            } else if TagRule::is_tag_attr(attr) {
+                self.tag_rules.push(TagRule::from_attr(attr)?);
+            } else if attr.path.is_ident("pending_if_empty") {
+                if !attr.tokens.is_empty() {
+                    return Err(parse::Error::new_spanned(
+                        &attr.tokens,
+                        "`#![pending_if_empty]` doesn't take any arguments",
+                    ));
+                }
+
+                self.pending_if_empty = true;
+            } else {
+                return Err(parse::Error::new_spanned(
+                    attr,
+                    "only `#![max_tests = ...]`, `#![tag(...)]` and \
+                     `#![pending_if_empty]` can be used at the top of a \
+                     describe block",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ToTokens for Describe {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.append_all(&self.items);
+        tokens.append_all(self.limit.quote_check(&self.sections));
+        tokens.append_all(focus::quote_check(&self.sections));
+
+        let mut scope = Scope::empty();
+        scope.push_tag_rules(&self.tag_rules);
+
+        if self.pending_if_empty {
+            scope.push_pending_if_empty();
+        }
+
+        if self.sections.iter().any(Section::has_focus) {
+            scope.push_focus_mode();
+        }
+
+        let mut names = SiblingNames::default();
+
+        for section in &self.sections {
+            match names.insert(section) {
+                Ok(()) => tokens.append_all(section.quote_inner(scope.clone())),
+                Err(err) => tokens.append_all(err.to_compile_error()),
+            }
+        }
+    }
+}
+
+impl Parse for Describe {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let mut describe = Describe::new(vec![], vec![]);
+        describe.configure(&input.call(syn::Attribute::parse_inner)?)?;
+
+        loop {
+            if Section::peek(input) {
+                describe.sections.push(input.parse()?);
+            } else if input.is_empty() {
+                break;
+            } else {
+                describe.items.push(input.parse()?);
+            }
+        }
+
+        Ok(describe)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(
+        r#"
+            section "tests" {
+                case "one" {
+                    assert!(true);
+                }
+            }
+        "#,
+        quote!(
+            mod section_tests {
+                use super::*;
+
+                #[test]
+                fn case_one() {
+                    if ::catchr::__private::skip(&["section tests", "case one"], &[], false) {
+                        return;
+                    }
+
+                    {
+                        {
+                            assert!(true);
+                        }
+                    }
+                }
+            }
+        )
+        ; "single root"
+    )]
+    #[test_case(
+        r#"
+            use std::collections::HashMap;
+
+            section "first" {
+                case "one" {
+                    let map: HashMap<u32, u32> = helper();
+                    assert!(map.is_empty());
+                }
+            }
+
+            fn helper() -> HashMap<u32, u32> {
+                HashMap::new()
+            }
+
+            when "second" {
+                then "two" {
+                    assert!(helper().is_empty());
+                }
+            }
+        "#,
+        quote!(
+            use std::collections::HashMap;
+
+            fn helper() -> HashMap<u32, u32> {
+                HashMap::new()
+            }
+
+            mod section_first {
+                use super::*;
+
+                #[test]
+                fn case_one() {
+                    if ::catchr::__private::skip(&["section first", "case one"], &[], false) {
+                        return;
+                    }
+
+                    {
+                        {
+                            let map: HashMap<u32, u32> = helper();
+                            assert!(map.is_empty());
+                        }
+                    }
+                }
+            }
+
+            mod when_second {
+                use super::*;
+
+                #[test]
+                fn then_two() {
+                    if ::catchr::__private::skip(&["when second", "then two"], &[], false) {
+                        return;
+                    }
+
+                    {
+                        {
+                            assert!(helper().is_empty());
+                        }
+                    }
+                }
+            }
+        )
+        ; "multiple roots with items"
+    )]
+    #[test_case(
+        r#"
+            #![tag(slow, cfg_attr(not(feature = "slow"), ignore))]
+            #![tag(db, cfg(feature = "db"))]
+
+            section "tests" [slow] {
+                case "one" {}
+                case "two [db]" {}
+            }
+        "#,
+        quote!(
+            mod section_tests {
+                use super::*;
+
+                #[test]
+                #[cfg_attr(not(feature = "slow"), ignore)]
+                fn case_one() {
+                    if ::catchr::__private::skip(&["section tests", "case one"], &["slow"], false) {
+                        return;
+                    }
+
+                    {
+                        {}
+                    }
+                }
+
+                #[test]
+                #[cfg_attr(not(feature = "slow"), ignore)]
+                #[cfg(feature = "db")]
+                fn case_two() {
+                    if ::catchr::__private::skip(&["section tests", "case two"], &["slow", "db"], false) {
+                        return;
+                    }
+
+                    {
+                        {}
+                    }
+                }
+            }
+        )
+        ; "tag rules"
+    )]
+    #[test_case(
+        r#"
+            #![pending_if_empty]
+
+            section "tests" {
+                case "one" {}
+                case "two" {
+                    assert!(true);
+                }
+            }
+        "#,
+        quote!(
+            mod section_tests {
+                use super::*;
+
+                #[test]
+                #[ignore = "pending: one"]
+                fn case_one() {}
+
+                #[test]
+                fn case_two() {
+                    if ::catchr::__private::skip(&["section tests", "case two"], &[], false) {
+                        return;
+                    }
+
+                    {
+                        {
+                            assert!(true);
+                        }
+                    }
+                }
+            }
+        )
+        ; "pending if empty"
+    )]
+    fn parse_and_quote(s: &str, exp: TokenStream) {
+        let describe = syn::parse_str::<Describe>(s).unwrap();
+        let describe = describe.to_token_stream();
+
+        assert_eq!(exp.to_string(), describe.to_string());
+    }
+
+    #[test_case("#![max_tests = 10]" => TestLimit::new(10) ; "set")]
+    #[test_case(
+        "#![max_tests = 10] #![max_tests = 20]" => TestLimit::new(20)
+        ; "last one wins"
+    )]
+    #[test_case("" => TestLimit::default() ; "default")]
+    fn test_limit(s: &str) -> TestLimit {
+        *syn::parse_str::<Describe>(s).unwrap().limit()
+    }
+
+    #[test_case("#![allow(unused)]" ; "other attribute")]
+    #[test_case("#![max_tests = \"10\"]" ; "not a number")]
+    #[test_case("#![max_tests]" ; "no value")]
+    #[test_case("#![tag(slow)]" ; "tag without attribute")]
+    #[test_case("#![pending_if_empty(true)]" ; "pending with arguments")]
+    fn config_error(s: &str) {
+        syn::parse_str::<Describe>(s).unwrap_err();
+    }
+
+    #[test]
+    fn empty() {
+        let describe = syn::parse_str::<Describe>("").unwrap();
+
+        assert_eq!(describe, Describe::new(vec![], vec![]));
+        assert!(describe.to_token_stream().is_empty());
+    }
+}
@@ -0,0 +1,260 @@
+use proc_macro2::{Delimiter, Group, Span, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::parse::{self, Parse, ParseStream};
+use syn::Token;
+
+use crate::catchr_mode::CatchrMode;
+use crate::runtime;
+use crate::scope::Scope;
+use crate::section::Section;
+use crate::section_body::SectionBody;
+use crate::section_keyword::SectionKeyword;
+
+/// A section whose description is computed at runtime, so that sections can
+/// be created in a loop, e.g.
+///
+/// ```text
+/// for user in fixtures() {
+///     dynamic_section!(format!("user {}", user.id), {
+///         assert!(user.is_valid());
+///     });
+/// }
+/// ```
+///
+/// Since the tests can't be generated for descriptions that aren't known
+/// yet, dynamic sections can only be used within runtime sections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynamicSection {
+    name: syn::Expr,
+    body: SectionBody,
+}
+
+impl DynamicSection {
+    pub fn new(name: syn::Expr, body: SectionBody) -> Self {
+        Self { name, body }
+    }
+
+    pub fn name(&self) -> &syn::Expr {
+        &self.name
+    }
+
+    pub fn body(&self) -> &SectionBody {
+        &self.body
+    }
+
+    /// Looks for a dynamic section, returning the span of its macro name.
+    pub fn find(tokens: TokenStream) -> Option<Span> {
+        let tokens: Vec<_> = tokens.into_iter().collect();
+
+        tokens.iter().enumerate().find_map(|(idx, token)| {
+            if Self::call_args(&tokens[idx..]).is_some() {
+                return Some(token.span());
+            }
+
+            match token {
+                TokenTree::Group(group) => Self::find(group.stream()),
+                _ => None,
+            }
+        })
+    }
+
+    /// The arguments of a `dynamic_section!` call at the start of `tokens`.
+    fn call_args(tokens: &[TokenTree]) -> Option<&Group> {
+        let (ident, bang, args) = match tokens {
+            [TokenTree::Ident(ident), bang, TokenTree::Group(args), ..] => {
+                (ident, bang, args)
+            }
+            _ => return None,
+        };
+
+        let is_call = ident == "dynamic_section"
+            && matches!(bang, TokenTree::Punct(bang) if bang.as_char() == '!')
+            && args.delimiter() == Delimiter::Parenthesis;
+
+        is_call.then_some(args)
+    }
+
+    /// Replaces the dynamic sections within a statement of a runtime section
+    /// with blocks entered when the tracker picks them.
+    pub fn lower_stmt(
+        stmt: &syn::Stmt,
+        scope: &Scope,
+        mode: &CatchrMode,
+    ) -> parse::Result<syn::Stmt> {
+        let tokens = stmt.to_token_stream();
+
+        if Self::find(tokens.clone()).is_none() {
+            return Ok(stmt.clone());
+        }
+
+        syn::parse2(Self::lower(tokens, scope, mode)?)
+    }
+
+    fn lower(
+        tokens: TokenStream,
+        scope: &Scope,
+        mode: &CatchrMode,
+    ) -> parse::Result<TokenStream> {
+        let tokens: Vec<_> = tokens.into_iter().collect();
+        let mut lowered = TokenStream::new();
+        let mut idx = 0;
+
+        while idx < tokens.len() {
+            if let Some(args) = Self::call_args(&tokens[idx..]) {
+                let section: Self = syn::parse2(args.stream())?;
+                lowered.extend(section.quote(scope, mode)?);
+                idx += 3;
+
+                // The section becomes an `if` block, which doesn't need a
+                // semicolon
+                if let Some(TokenTree::Punct(punct)) = tokens.get(idx) {
+                    if punct.as_char() == ';' {
+                        idx += 1;
+                    }
+                }
+
+                continue;
+            }
+
+            lowered.extend(Some(match &tokens[idx] {
+                TokenTree::Group(group) => {
+                    let mut lowered = Group::new(
+                        group.delimiter(),
+                        Self::lower(group.stream(), scope, mode)?,
+                    );
+                    lowered.set_span(group.span());
+
+                    TokenTree::Group(lowered)
+                }
+                token => token.clone(),
+            }));
+
+            idx += 1;
+        }
+
+        Ok(lowered)
+    }
+
+    fn quote(
+        &self,
+        scope: &Scope,
+        mode: &CatchrMode,
+    ) -> parse::Result<TokenStream> {
+        let name = &self.name;
+
+        // Nested sections are entered the same way as in a regular section
+        let section =
+            Section::new(SectionKeyword::Section, "", self.body.clone())
+                .with_mode(mode.clone());
+
+        let title = quote! {
+            &::std::format!(
+                "section {:?}",
+                ::std::string::ToString::to_string(&(#name)),
+            )
+        };
+
+        Ok(
+            runtime::quote_enter(title, section.quote_runtime_body(scope)?)
+                .into_token_stream(),
+        )
+    }
+}
+
+impl Parse for DynamicSection {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![,]>()?;
+
+        let content;
+        syn::braced!(content in input);
+        let body = content.parse()?;
+
+        input.parse::<Option<Token![,]>>()?;
+
+        Ok(Self::new(name, body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+    use crate::section_item::SectionItem;
+
+    #[test]
+    fn parse() {
+        let section: DynamicSection =
+            syn::parse_str("format!(\"user {}\", id), { check(id); },")
+                .unwrap();
+
+        assert_eq!(
+            section,
+            DynamicSection::new(
+                parse_quote!(format!("user {}", id)),
+                SectionBody::new(vec![SectionItem::Stmt(parse_quote!(
+                    check(id);
+                ))]),
+            )
+        );
+    }
+
+    #[test]
+    fn lower_stmt() {
+        let stmt: syn::Stmt = parse_quote! {
+            for id in ids {
+                dynamic_section!(id, {
+                    then "it's valid" {
+                        check(id);
+                    }
+                });
+            }
+        };
+
+        let stmt = DynamicSection::lower_stmt(
+            &stmt,
+            &Scope::empty(),
+            &CatchrMode::Regular,
+        )
+        .unwrap();
+
+        assert_eq!(
+            stmt.to_token_stream().to_string(),
+            quote!(for id in ids {
+                if __catchr_tracker.enter(&::std::format!(
+                    "section {:?}",
+                    ::std::string::ToString::to_string(&(id)),
+                )) {
+                    {
+                        if __catchr_tracker.enter("then \"it's valid\"") {
+                            {
+                                check(id);
+                            }
+                            #[allow(unreachable_code)]
+                            __catchr_tracker.leave();
+                        }
+                    }
+                    #[allow(unreachable_code)]
+                    __catchr_tracker.leave();
+                }
+            })
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn ignores_other_stmts() {
+        let stmt: syn::Stmt = parse_quote!(section!(id, {}););
+
+        assert_eq!(
+            DynamicSection::lower_stmt(
+                &stmt,
+                &Scope::empty(),
+                &CatchrMode::Regular
+            )
+            .unwrap(),
+            stmt
+        );
+    }
+}
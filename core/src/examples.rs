@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+
+use proc_macro2::TokenTree;
+use quote::ToTokens;
+use syn::parse::{self, Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parse_quote, Token};
+
+mod kw {
+    syn::custom_keyword!(with);
+}
+
+/// An example table of a data-driven section:
+///
+/// ```text
+/// case "adds {a} and {b}" with [(1, 2, 3), (4, 5, 9)] as (a, b, expected) {
+///     assert_eq!(a + b, expected);
+/// }
+/// ```
+///
+/// Every row is bound to the pattern and expands into its own copy of the
+/// section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Examples {
+    rows: Vec<syn::Expr>,
+    pat: syn::Pat,
+}
+
+impl Examples {
+    pub fn new(rows: Vec<syn::Expr>, pat: syn::Pat) -> Self {
+        Self { rows, pat }
+    }
+
+    pub fn peek(input: ParseStream) -> bool {
+        input.peek(kw::with)
+    }
+
+    pub fn rows(&self) -> &[syn::Expr] {
+        &self.rows
+    }
+
+    pub fn pat(&self) -> &syn::Pat {
+        &self.pat
+    }
+
+    /// The statement binding the values of a row, e.g.
+    /// `let (a, b, expected) = (1, 2, 3);`.
+    pub fn binding(&self, row: &syn::Expr) -> syn::Stmt {
+        let pat = &self.pat;
+
+        parse_quote!(let #pat = #row;)
+    }
+
+    /// Replaces the `{name}` placeholders in the description with the values
+    /// bound to `name` by the row. Placeholders that can't be resolved (for
+    /// example because the value isn't a tuple literal matching the pattern)
+    /// are left as they are. The values aren't interpolated themselves, even
+    /// if they contain placeholders.
+    pub fn interpolate(&self, description: &str, row: &syn::Expr) -> String {
+        let mut values = HashMap::new();
+        collect_values(&self.pat, row, &mut values);
+
+        let mut interpolated = String::new();
+        let mut rest = description;
+
+        while let Some(start) = rest.find('{') {
+            interpolated.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let value = rest
+                .find('}')
+                .and_then(|end| Some((end, values.get(&rest[1..end])?)));
+
+            match value {
+                Some((end, value)) => {
+                    interpolated.push_str(value);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    interpolated.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+
+        interpolated.push_str(rest);
+        interpolated
+    }
+}
+
+fn collect_values(
+    pat: &syn::Pat,
+    expr: &syn::Expr,
+    values: &mut HashMap<String, String>,
+) {
+    match (pat, expr) {
+        (_, syn::Expr::Paren(expr)) => collect_values(pat, &expr.expr, values),
+        (_, syn::Expr::Group(expr)) => collect_values(pat, &expr.expr, values),
+        (syn::Pat::Ident(pat), _) if pat.subpat.is_none() => {
+            values.insert(pat.ident.to_string(), quote_value(expr));
+        }
+        (syn::Pat::Tuple(pat), syn::Expr::Tuple(expr))
+            if pat.elems.len() == expr.elems.len() =>
+        {
+            for (pat, expr) in pat.elems.iter().zip(&expr.elems) {
+                collect_values(pat, expr, values);
+            }
+        }
+        (syn::Pat::Struct(pat), syn::Expr::Struct(expr)) => {
+            for field in &pat.fields {
+                let value = expr
+                    .fields
+                    .iter()
+                    .find(|value| value.member == field.member);
+
+                if let Some(value) = value {
+                    collect_values(&field.pat, &value.expr, values);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Checks for a struct pattern without a path, like `Point { x, y }` - which,
+/// unlike an identifier, is followed by the body (or the return type) of the
+/// section.
+fn peek_struct_pat(input: ParseStream) -> bool {
+    let fork = input.fork();
+
+    fork.parse::<syn::Ident>().is_ok()
+        && fork.parse::<TokenTree>().is_ok()
+        && (fork.peek(syn::token::Brace) || fork.peek(Token![->]))
+}
+
+fn quote_value(expr: &syn::Expr) -> String {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(s),
+            ..
+        }) => s.value(),
+        expr => expr.to_token_stream().to_string(),
+    }
+}
+
+impl Parse for Examples {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        input.parse::<kw::with>()?;
+
+        let content;
+        syn::bracketed!(content in input);
+        let rows: Punctuated<syn::Expr, Token![,]> =
+            content.parse_terminated(syn::Expr::parse)?;
+
+        input.parse::<Token![as]>()?;
+
+        // A plain identifier followed by the body would otherwise be parsed
+        // as a struct pattern
+        let pat = if input.peek(syn::Ident)
+            && input.peek2(syn::token::Brace)
+            && !peek_struct_pat(input)
+        {
+            let ident: syn::Ident = input.parse()?;
+            parse_quote!(#ident)
+        } else {
+            input.parse()?
+        };
+
+        Ok(Self::new(rows.into_iter().collect(), pat))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    /// Parses the examples of a section, followed by its body.
+    fn parse_examples(s: &str) -> Examples {
+        syn::parse::Parser::parse_str(
+            |input: ParseStream| {
+                let examples: Examples = input.parse()?;
+                input.parse::<proc_macro2::TokenStream>()?;
+
+                Ok(examples)
+            },
+            s,
+        )
+        .unwrap()
+    }
+
+    #[test_case("adds {a} and {b}", "(a, b, c)", "(1, 2, 3)" => "adds 1 and 2" ; "tuple")]
+    #[test_case("adds {a} and {b}", "((a, b), c)", "((1, 2), 3)" => "adds 1 and 2" ; "nested tuple")]
+    #[test_case("greets {name}", "name", "\"Bob\"" => "greets Bob" ; "ident")]
+    #[test_case("adds {a} and {b}", "(a, b)", "pair()" => "adds {a} and {b}" ; "non literal row")]
+    #[test_case("adds {a} and {c}", "(a, b)", "(1, 2)" => "adds 1 and {c}" ; "unknown placeholder")]
+    #[test_case("{a} and {b}", "(a, b)", "(\"{b}\", \"{a}\")" => "{b} and {a}" ; "placeholders in values")]
+    #[test_case("{{a}} and {b", "(a, b)", "(1, 2)" => "{1} and {b" ; "unbalanced braces")]
+    #[test_case("at {x}, {y}", "Point { x, y: (y, _) }", "Point { y: (2, 3), x: 1 }" => "at 1, 2" ; "struct pattern")]
+    fn interpolate(description: &str, pat: &str, row: &str) -> String {
+        let examples =
+            parse_examples(&format!("with [{}] as {} {{}}", row, pat));
+
+        examples.interpolate(description, &examples.rows()[0])
+    }
+
+    #[test]
+    fn parse() {
+        let examples =
+            syn::parse_str::<Examples>("with [(1, 2), (3, 4)] as (a, b)")
+                .unwrap();
+
+        assert_eq!(
+            examples,
+            Examples::new(
+                vec![parse_quote!((1, 2)), parse_quote!((3, 4))],
+                parse_quote!((a, b)),
+            )
+        );
+    }
+
+    #[test_case("with [p] as p {}" => "p" ; "ident")]
+    #[test_case("with [p] as Point { x, y } {}" => "Point { x , y }" ; "struct pattern")]
+    #[test_case("with [p] as Point { x, y } -> Result<(), E> {}" => "Point { x , y }" ; "struct pattern with return type")]
+    #[test_case("with [p] as geo::Point { x, y } {}" => "geo :: Point { x , y }" ; "struct pattern with path")]
+    fn parse_pat(s: &str) -> String {
+        parse_examples(s).pat().to_token_stream().to_string()
+    }
+
+    #[test]
+    fn binding() {
+        let examples =
+            syn::parse_str::<Examples>("with [(1, 2)] as (a, b)").unwrap();
+
+        assert_eq!(
+            examples.binding(&examples.rows()[0]),
+            parse_quote!(let (a, b) = (1, 2);)
+        );
+    }
+}
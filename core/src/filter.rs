@@ -0,0 +1,111 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::tags::Tags;
+
+/// The start of a test, returning early when it's filtered out by the
+/// `CATCHR_FILTER` or `CATCHR_TAGS` environment variables - see
+/// `catchr::__private::skip`. `path` holds the descriptions of the sections
+/// the test is in, prefixed with their keywords, e.g. `when x is 1`.
+///
+/// Tests with `#[should_panic]` would fail by returning early, so they panic
+/// instead, with a message containing the expected one. `runtime` is set for
+/// tests with runtime sections, which run the sections nested deeper than
+/// their path themselves.
+pub fn quote_skip(
+    path: &[String],
+    tags: &Tags,
+    attrs: &[syn::Attribute],
+    returns: bool,
+    runtime: bool,
+) -> TokenStream {
+    let tags = tags.names();
+
+    let ret = match expected_panic(attrs) {
+        Some(expected) => {
+            let message = format!("catchr: skipped (expected: {})", expected);
+
+            quote!(::std::panic!(#message);)
+        }
+        None if returns => quote!(return ::std::result::Result::Ok(());),
+        None => quote!(return;),
+    };
+
+    quote! {
+        if ::catchr::__private::skip(&[#(#path),*], &[#(#tags),*], #runtime) {
+            #ret
+        }
+    }
+}
+
+/// The message a test with `#[should_panic]` is expected to panic with -
+/// empty if any panic will do. `None` for the other tests.
+fn expected_panic(attrs: &[syn::Attribute]) -> Option<String> {
+    let attr = attrs
+        .iter()
+        .find(|attr| attr.path.is_ident("should_panic"))?;
+
+    let expected = match attr.parse_meta().ok()? {
+        syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Str(expected),
+            ..
+        }) => Some(expected),
+        syn::Meta::List(list) => {
+            list.nested.into_iter().find_map(|nested| match nested {
+                syn::NestedMeta::Meta(syn::Meta::NameValue(
+                    syn::MetaNameValue {
+                        path,
+                        lit: syn::Lit::Str(expected),
+                        ..
+                    },
+                )) if path.is_ident("expected") => Some(expected),
+                _ => None,
+            })
+        }
+        _ => None,
+    };
+
+    Some(
+        expected
+            .map(|expected| expected.value())
+            .unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(parse_quote!(#[ignore]) => None ; "other attribute")]
+    #[test_case(parse_quote!(#[should_panic]) => Some("".into()) ; "any")]
+    #[test_case(
+        parse_quote!(#[should_panic(expected = "boom")])
+        => Some("boom".into())
+        ; "expected"
+    )]
+    #[test_case(
+        parse_quote!(#[should_panic = "boom"]) => Some("boom".into())
+        ; "name value"
+    )]
+    fn expected_panic(attr: syn::Attribute) -> Option<String> {
+        super::expected_panic(&[attr])
+    }
+
+    #[test]
+    fn should_panic_tests_panic_when_skipped() {
+        let skip = quote_skip(
+            &["then x".to_string()],
+            &Tags::default(),
+            &[parse_quote!(#[should_panic(expected = "boom")])],
+            true,
+            false,
+        );
+
+        assert!(skip
+            .to_string()
+            .contains(r#"panic ! ("catchr: skipped (expected: boom)")"#));
+    }
+}
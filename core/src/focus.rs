@@ -0,0 +1,113 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use syn::parse;
+
+use crate::section::Section;
+use crate::section_item::SectionItem;
+
+/// Checks for the `#[focus]` attribute, which focuses a section the same way
+/// as prefixing its keyword with `f` (like `fthen`) does. When any section of
+/// a `describe!` block is focused, only the tests within the focused sections
+/// run - the others are ignored.
+pub fn is_focus_attr(attr: &syn::Attribute) -> bool {
+    attr.path.is_ident("focus")
+}
+
+/// Raises a warning for every focused section (through the use of a
+/// deprecated constant, as proc macros can't emit warnings on stable), so
+/// that they don't get committed unnoticed. With the `deny-focus` feature,
+/// they're errors instead.
+pub fn quote_check(sections: &[Section]) -> TokenStream {
+    let mut focused = vec![];
+
+    for section in sections {
+        collect_focused(section, &mut focused);
+    }
+
+    focused
+        .into_iter()
+        .map(|section| {
+            if cfg!(feature = "deny-focus") {
+                let message = format!(
+                    "`{}` is focused, which the `deny-focus` feature of \
+                     catchr doesn't allow",
+                    section.title(),
+                );
+
+                return parse::Error::new(section.keyword_span(), message)
+                    .to_compile_error();
+            }
+
+            let message = format!(
+                "`{}` is focused, so the other tests of this block are \
+                 ignored - remove the focus once you're done with it",
+                section.title(),
+            );
+
+            let warning = Ident::new("FOCUSED", section.keyword_span());
+
+            quote! {
+                const _: () = {
+                    #[deprecated(note = #message)]
+                    const #warning: () = ();
+                    #warning
+                };
+            }
+        })
+        .collect()
+}
+
+fn collect_focused<'a>(section: &'a Section, focused: &mut Vec<&'a Section>) {
+    if section.is_focused() {
+        focused.push(section);
+    }
+
+    for item in section.body().items() {
+        if let SectionItem::Sep(section) = item {
+            collect_focused(section, focused);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(s: &str) -> String {
+        let describe = syn::parse_str::<crate::Describe>(s).unwrap();
+
+        quote_check(describe.sections()).to_string()
+    }
+
+    #[test]
+    fn reports_every_focused_section() {
+        let check = check(
+            r#"
+                fsection "a" {
+                    #[focus]
+                    then "b" {}
+                    then "c" {}
+                }
+
+                section "d" {
+                    then "e" {}
+                }
+            "#,
+        );
+
+        let reported = if cfg!(feature = "deny-focus") {
+            "compile_error"
+        } else {
+            "deprecated"
+        };
+
+        assert_eq!(check.matches(reported).count(), 2);
+        assert!(check.contains(r#"`section \"a\"` is focused"#));
+        assert!(check.contains(r#"`then \"b\"` is focused"#));
+    }
+
+    #[test]
+    fn nothing_focused() {
+        assert!(check(r#"section "a" { then "b" {} }"#).is_empty());
+    }
+}
@@ -0,0 +1,131 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::parse::{self, Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parse_quote, Token};
+
+/// Inputs of a property-based `forall` section, e.g.
+///
+/// ```text
+/// forall "reversing twice is identity" (v in vec(0..100u32, 0..10)) {
+///     assert_eq!(reverse(reverse(v.clone())), v);
+/// }
+/// ```
+///
+/// Every test in the section is run by proptest, with the values drawn from
+/// the strategies bound to the patterns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Forall {
+    inputs: Vec<ForallInput>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ForallInput {
+    pat: syn::Pat,
+    strategy: syn::Expr,
+}
+
+impl Forall {
+    pub fn peek(input: ParseStream) -> bool {
+        input.peek(syn::token::Paren)
+    }
+
+    fn input_ident() -> Ident {
+        Ident::new("__catchr_input", Span::call_site())
+    }
+
+    /// The statement binding the generated values, e.g.
+    /// `let (a, s) = __catchr_input;`.
+    pub fn binding(&self) -> syn::Stmt {
+        let pats = self.inputs.iter().map(|input| &input.pat);
+        let input = Self::input_ident();
+
+        parse_quote!(let (#(#pats,)*) = #input;)
+    }
+
+    /// Wraps the body of a test, so that it's run by proptest. Failures are
+    /// reported together with the path of the test and the minimal failing
+    /// input.
+    pub fn quote_runs(&self, inner: TokenStream, path: &str) -> TokenStream {
+        let strategies = self.inputs.iter().map(|input| &input.strategy);
+        let input = Self::input_ident();
+
+        quote! {
+            let mut __catchr_runner =
+                ::catchr::__private::proptest::test_runner::TestRunner::new(
+                    ::catchr::__private::proptest::test_runner::Config {
+                        source_file: ::std::option::Option::Some(file!()),
+                        ..::std::default::Default::default()
+                    },
+                );
+
+            let __catchr_result =
+                __catchr_runner.run(&(#(#strategies,)*), |#input| {
+                    #inner
+
+                    ::std::result::Result::Ok(())
+                });
+
+            if let ::std::result::Result::Err(err) = __catchr_result {
+                panic!("{}: {}", #path, err);
+            }
+        }
+    }
+}
+
+impl Parse for Forall {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let content;
+        syn::parenthesized!(content in input);
+
+        let inputs: Punctuated<ForallInput, Token![,]> =
+            content.parse_terminated(ForallInput::parse)?;
+
+        Ok(Self {
+            inputs: inputs.into_iter().collect(),
+        })
+    }
+}
+
+impl Parse for ForallInput {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let pat = input.parse()?;
+        input.parse::<Token![in]>()?;
+        let strategy = input.parse()?;
+
+        Ok(Self { pat, strategy })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binding() {
+        let forall =
+            syn::parse_str::<Forall>(r#"(a in 0..100u32, s in "[a-z]+")"#)
+                .unwrap();
+
+        assert_eq!(
+            forall.binding(),
+            parse_quote!(let (a, s,) = __catchr_input;)
+        );
+    }
+
+    #[test]
+    fn binding_with_patterns() {
+        let forall =
+            syn::parse_str::<Forall>("((a, b) in (0..10, 0..10))").unwrap();
+
+        assert_eq!(
+            forall.binding(),
+            parse_quote!(let ((a, b),) = __catchr_input;)
+        );
+    }
+
+    #[test]
+    fn rejects_inputs_without_strategies() {
+        assert!(syn::parse_str::<Forall>("(a, b)").is_err());
+    }
+}
@@ -0,0 +1,245 @@
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::parse::{self, Parser};
+use syn::punctuated::Punctuated;
+use syn::{parse_quote, Token};
+
+/// A Catch2-style generator statement, e.g. `let x = generate!(1, 5, 10);`.
+///
+/// A test reaching generators is run once for every combination of their
+/// values. Besides a list of values, a generator can be given a `range(a, b)`
+/// or a `from_iter(iterable)`, followed by any iterator adapters, e.g.
+/// `generate!(range(0, 10).filter(|x| x % 3 == 0).take(2))`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generate {
+    pat: syn::Pat,
+    values: syn::Expr,
+}
+
+impl Generate {
+    /// Recognizes `let <pat> = generate!(...);` statements. Generators used
+    /// in any other way are reported as errors.
+    pub fn from_stmt(stmt: &syn::Stmt) -> parse::Result<Option<Self>> {
+        let init = match stmt {
+            syn::Stmt::Local(local) => {
+                local.init.as_ref().map(|(_, expr)| (local, &**expr))
+            }
+            _ => None,
+        };
+
+        let (local, mac) = match init {
+            Some((local, syn::Expr::Macro(mac)))
+                if mac.mac.path.is_ident("generate") =>
+            {
+                (local, &mac.mac)
+            }
+            _ => return Self::check_unsupported(stmt).map(|()| None),
+        };
+
+        let values = Punctuated::<syn::Expr, Token![,]>::parse_terminated
+            .parse2(mac.tokens.clone())?;
+
+        let values = match values.len() {
+            0 => {
+                return Err(parse::Error::new_spanned(
+                    mac,
+                    "generate! needs at least one value",
+                ))
+            }
+            1 => {
+                let value = values.into_iter().next().unwrap();
+
+                match Self::quote_iterator(&value) {
+                    Some(values) => values,
+                    None => parse_quote!([#value]),
+                }
+            }
+            _ => {
+                let values = values.into_iter();
+                parse_quote!([#(#values),*])
+            }
+        };
+
+        Ok(Some(Self {
+            pat: local.pat.clone(),
+            values,
+        }))
+    }
+
+    fn check_unsupported(stmt: &syn::Stmt) -> parse::Result<()> {
+        match Self::find(stmt.to_token_stream()) {
+            Some(span) => Err(parse::Error::new(
+                span,
+                "`generate!` must be used as `let <pat> = generate!(..);`",
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Looks for a `generate!` call, returning the span of its macro name.
+    fn find(tokens: TokenStream) -> Option<Span> {
+        let tokens: Vec<_> = tokens.into_iter().collect();
+
+        tokens
+            .iter()
+            .enumerate()
+            .find_map(|(idx, token)| match token {
+                TokenTree::Ident(ident) if ident == "generate" => {
+                    match tokens.get(idx + 1) {
+                        Some(TokenTree::Punct(bang))
+                            if bang.as_char() == '!' =>
+                        {
+                            Some(ident.span())
+                        }
+                        _ => None,
+                    }
+                }
+                TokenTree::Group(group) => Self::find(group.stream()),
+                _ => None,
+            })
+    }
+
+    /// Turns `range(a, b)` or `from_iter(iterable)` at the root of a chain of
+    /// iterator adapters into an actual iterator.
+    fn quote_iterator(expr: &syn::Expr) -> Option<syn::Expr> {
+        match expr {
+            syn::Expr::MethodCall(call) => {
+                let receiver = Self::quote_iterator(&call.receiver)?;
+
+                Some(syn::Expr::MethodCall(syn::ExprMethodCall {
+                    receiver: Box::new(receiver),
+                    ..call.clone()
+                }))
+            }
+            syn::Expr::Call(call) => match &*call.func {
+                syn::Expr::Path(path)
+                    if path.path.is_ident("range") && call.args.len() == 2 =>
+                {
+                    let start = &call.args[0];
+                    let end = &call.args[1];
+
+                    Some(parse_quote!((#start..#end)))
+                }
+                syn::Expr::Path(path)
+                    if path.path.is_ident("from_iter")
+                        && call.args.len() == 1 =>
+                {
+                    let iterable = &call.args[0];
+
+                    Some(parse_quote!(
+                        ::std::iter::IntoIterator::into_iter(#iterable)
+                    ))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The name under which the picked values are reported.
+    fn name(&self) -> String {
+        let pat = match &self.pat {
+            syn::Pat::Type(pat) => &*pat.pat,
+            pat => pat,
+        };
+
+        pat.to_token_stream().to_string()
+    }
+
+    /// The identifier of the generator tracker within a generated test.
+    pub fn tracker() -> Ident {
+        Ident::new("__catchr_generators", Span::call_site())
+    }
+
+    /// Wraps the body of a test reaching generators, so that it's run for
+    /// every combination of their values.
+    pub fn quote_runs(inner: TokenStream) -> TokenStream {
+        let tracker = Self::tracker();
+
+        quote! {
+            let mut #tracker = ::catchr::__private::Generators::new();
+
+            while #tracker.next_run() {
+                #inner
+            }
+        }
+    }
+
+    pub fn to_stmt(&self) -> syn::Stmt {
+        let tracker = Self::tracker();
+        let pat = &self.pat;
+        let name = self.name();
+        let values = &self.values;
+
+        parse_quote!(let #pat = #tracker.pick(#name, #values);)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(
+        "let x = generate!(1, 5, 10);",
+        "let x = __catchr_generators.pick(\"x\", [1, 5, 10]);"
+        ; "values"
+    )]
+    #[test_case(
+        "let x: u32 = generate!(7);",
+        "let x: u32 = __catchr_generators.pick(\"x\", [7]);"
+        ; "single value"
+    )]
+    #[test_case(
+        "let (a, b) = generate!((1, 2), (3, 4));",
+        "let (a, b) = __catchr_generators.pick(\"(a , b)\", [(1, 2), (3, 4)]);"
+        ; "pattern"
+    )]
+    #[test_case(
+        "let x = generate!(range(0, 10).filter(|x| x % 2 == 0).map(|x| x * 2).take(3));",
+        "let x = __catchr_generators.pick(\"x\", (0..10).filter(|x| x % 2 == 0).map(|x| x * 2).take(3));"
+        ; "range with adapters"
+    )]
+    #[test_case(
+        "let name = generate!(from_iter(names()).take(2));",
+        "let name = __catchr_generators.pick(\"name\", ::std::iter::IntoIterator::into_iter(names()).take(2));"
+        ; "from iter"
+    )]
+    fn to_stmt(stmt: &str, exp: &str) {
+        let stmt: syn::Stmt = syn::parse_str(stmt).unwrap();
+        let exp: syn::Stmt = syn::parse_str(exp).unwrap();
+
+        let generate = Generate::from_stmt(&stmt).unwrap().unwrap();
+
+        assert_eq!(generate.to_stmt(), exp);
+    }
+
+    #[test_case("let x = 1;"               ; "plain let")]
+    #[test_case("let x = vec![1, 2];"      ; "other macro")]
+    #[test_case("let generate = 1;"        ; "generate variable")]
+    fn ignores_other_stmts(stmt: &str) {
+        let stmt: syn::Stmt = syn::parse_str(stmt).unwrap();
+
+        assert_eq!(Generate::from_stmt(&stmt).unwrap(), None);
+    }
+
+    #[test_case("generate!(1, 2);"             ; "not a let")]
+    #[test_case("let x = foo(generate!(1));"   ; "nested")]
+    #[test_case("if y { generate!(1); }"       ; "in a block")]
+    fn rejects_unsupported_uses(stmt: &str) {
+        let stmt: syn::Stmt = syn::parse_str(stmt).unwrap();
+
+        assert_eq!(
+            Generate::from_stmt(&stmt).unwrap_err().to_string(),
+            "`generate!` must be used as `let <pat> = generate!(..);`"
+        );
+    }
+
+    #[test]
+    fn rejects_empty_generator() {
+        let stmt: syn::Stmt = syn::parse_str("let x = generate!();").unwrap();
+
+        assert!(Generate::from_stmt(&stmt).is_err());
+    }
+}
@@ -1,14 +1,47 @@
 mod catchr_mode;
+mod defer;
+mod describe;
+mod dynamic_section;
+mod examples;
+mod filter;
+mod focus;
+mod forall;
+mod generate;
+mod returns;
+mod runtime;
 mod scope;
 mod section;
 mod section_body;
 mod section_item;
 mod section_keyword;
+mod section_mark;
+mod shared;
+mod sibling_names;
+mod spec;
+mod tag_rule;
+mod tags;
+mod test_limit;
+mod timeout;
+mod tokio_args;
 mod utils;
 
 pub use self::catchr_mode::CatchrMode;
+pub use self::defer::Defer;
+pub use self::describe::Describe;
+pub use self::dynamic_section::DynamicSection;
+pub use self::examples::Examples;
+pub use self::forall::Forall;
+pub use self::generate::Generate;
+pub use self::returns::Returns;
 pub use self::scope::Scope;
 pub use self::section::Section;
 pub use self::section_body::SectionBody;
 pub use self::section_item::SectionItem;
 pub use self::section_keyword::SectionKeyword;
+pub use self::section_mark::SectionMark;
+pub use self::spec::Spec;
+pub use self::tag_rule::TagRule;
+pub use self::tags::Tags;
+pub use self::test_limit::TestLimit;
+pub use self::timeout::Timeout;
+pub use self::tokio_args::TokioArgs;
@@ -0,0 +1,142 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::parse::{self, Parse, ParseStream};
+use syn::Token;
+
+use crate::catchr_mode::CatchrMode;
+
+/// The return type of the tests in a section, e.g.
+///
+/// ```text
+/// given "a config file" -> Result<(), io::Error> {
+///     let config = fs::read_to_string("config.toml")?;
+///     ...
+/// }
+/// ```
+///
+/// The tests return `Result`, so that `?` can be used anywhere within the
+/// section and its ancestors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Returns {
+    ty: syn::Type,
+}
+
+impl Returns {
+    pub fn new(ty: syn::Type) -> Self {
+        Self { ty }
+    }
+
+    pub fn peek(input: ParseStream) -> bool {
+        input.peek(Token![->])
+    }
+
+    pub fn ty(&self) -> &syn::Type {
+        &self.ty
+    }
+
+    fn result_ident() -> Ident {
+        Ident::new("__catchr_result", Span::call_site())
+    }
+
+    /// Evaluates the body of a test into a value of the return type. The
+    /// body is put in a closure (or an async block), so that `?` returns from
+    /// it, rather than from the test.
+    fn quote_result(
+        &self,
+        inner: TokenStream,
+        mode: &CatchrMode,
+    ) -> TokenStream {
+        let ty = &self.ty;
+        let result = Self::result_ident();
+
+        if !mode.is_async() {
+            quote! {
+                #[allow(clippy::redundant_closure_call)]
+                let #result: #ty = (|| -> #ty {
+                    #inner
+
+                    ::std::result::Result::Ok(())
+                })();
+            }
+        } else {
+            quote! {
+                let #result: #ty = async {
+                    #inner
+
+                    ::std::convert::identity::<#ty>(
+                        ::std::result::Result::Ok(())
+                    )
+                }
+                .await;
+            }
+        }
+    }
+
+    /// Runs the body of a test, returning early with the error (reported
+    /// together with the path of the test) if it fails.
+    pub fn quote_run(
+        &self,
+        inner: TokenStream,
+        mode: &CatchrMode,
+        path: &str,
+    ) -> TokenStream {
+        let result = Self::result_ident();
+        let run = self.quote_result(inner, mode);
+
+        quote! {
+            #run
+
+            if let ::std::result::Result::Err(err) = &#result {
+                eprintln!("catchr: {} failed: {:?}", #path, err);
+                return #result;
+            }
+        }
+    }
+
+    /// Runs the body of a test with `#[should_panic]`, which can't return an
+    /// error - so it panics with it (and the path of the test) instead.
+    pub fn quote_panicking_run(
+        &self,
+        inner: TokenStream,
+        mode: &CatchrMode,
+        path: &str,
+    ) -> TokenStream {
+        let result = Self::result_ident();
+        let run = self.quote_result(inner, mode);
+
+        quote! {
+            #run
+
+            if let ::std::result::Result::Err(err) = &#result {
+                panic!("catchr: {} failed: {:?}", #path, err);
+            }
+        }
+    }
+
+    /// Runs the body of a property-based test, turning an error into a
+    /// failure of the test case, so that proptest can shrink the input.
+    pub fn quote_property_run(&self, inner: TokenStream) -> TokenStream {
+        let result = Self::result_ident();
+        let run = self.quote_result(inner, &CatchrMode::Regular);
+
+        quote! {
+            #run
+
+            if let ::std::result::Result::Err(err) = #result {
+                return ::std::result::Result::Err(
+                    ::catchr::__private::proptest::test_runner::TestCaseError::fail(
+                        format!("{:?}", err),
+                    ),
+                );
+            }
+        }
+    }
+}
+
+impl Parse for Returns {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        input.parse::<Token![->]>()?;
+
+        Ok(Self::new(input.parse()?))
+    }
+}
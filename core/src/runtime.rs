@@ -0,0 +1,81 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, ToTokens};
+
+use crate::catchr_mode::CatchrMode;
+use crate::returns::Returns;
+
+/// Checks for the `#[runtime_sections]` attribute, which turns a section into
+/// a single test, run once for every path through its nested sections
+/// (picked at runtime by `catchr::__private::Tracker`), instead of a test for
+/// each of them.
+pub fn is_runtime_attr(attr: &syn::Attribute) -> bool {
+    attr.path.is_ident("runtime_sections")
+}
+
+fn tracker() -> Ident {
+    Ident::new("__catchr_tracker", Span::call_site())
+}
+
+/// A nested section, entered only on the runs the tracker picks it for.
+/// `title` is an expression of type `&str`.
+pub fn quote_enter(title: TokenStream, inner: TokenStream) -> syn::Stmt {
+    let tracker = tracker();
+
+    // Sections that always panic never leave
+    syn::parse_quote! {
+        if #tracker.enter(#title) {
+            #inner
+            #[allow(unreachable_code)]
+            #tracker.leave();
+        }
+    }
+}
+
+/// Runs the body of a test until the tracker has gone down every path,
+/// failing the test afterwards if any of the runs failed.
+pub fn quote_passes(
+    inner: TokenStream,
+    returns: Option<&Returns>,
+    mode: &CatchrMode,
+    path: &str,
+) -> TokenStream {
+    let tracker = tracker();
+
+    let ty = match returns {
+        Some(returns) => returns.ty().to_token_stream(),
+        None => quote! {
+            ::std::result::Result<(), ::std::convert::Infallible>
+        },
+    };
+
+    let run = if !mode.is_async() {
+        quote! {
+            ::catchr::__private::catch_unwind(|| -> #ty {
+                #inner
+
+                ::std::result::Result::Ok(())
+            })
+        }
+    } else {
+        quote! {
+            ::catchr::__private::catch_unwind_async(async {
+                #inner
+
+                ::std::convert::identity::<#ty>(::std::result::Result::Ok(()))
+            })
+            .await
+        }
+    };
+
+    quote! {
+        let #tracker = ::catchr::__private::Tracker::new(#path);
+
+        while #tracker.start_pass() {
+            #[allow(unreachable_code)]
+            let __catchr_outcome = #run;
+            #tracker.end_pass(__catchr_outcome);
+        }
+
+        #tracker.finish();
+    }
+}
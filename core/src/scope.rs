@@ -1,11 +1,35 @@
 use proc_macro2::TokenStream;
 use quote::quote;
 
+use crate::catchr_mode::CatchrMode;
+use crate::defer::Defer;
+use crate::forall::Forall;
+use crate::returns::Returns;
+use crate::section_item::SectionItem;
+use crate::tag_rule::TagRule;
+use crate::tags::Tags;
+use crate::timeout::Timeout;
+
 #[derive(Clone, Debug)]
 pub struct Scope {
-    before: Vec<syn::Stmt>,
+    before: Vec<SectionItem>,
     inner: Option<Box<Scope>>,
-    after: Vec<syn::Stmt>,
+    after: Vec<SectionItem>,
+
+    attrs: Vec<syn::Attribute>,
+    tags: Tags,
+    tag_rules: Vec<TagRule>,
+    pending: bool,
+    pending_if_empty: bool,
+    focus_mode: bool,
+    focused: bool,
+    generators: bool,
+    forall: Option<Forall>,
+    returns: Option<Returns>,
+    timeout: Option<Timeout>,
+    shared: Option<Vec<usize>>,
+    path: Vec<String>,
+    descriptions: Vec<String>,
 }
 
 impl Scope {
@@ -14,18 +38,46 @@ impl Scope {
             before: vec![],
             inner: None,
             after: vec![],
+            attrs: vec![],
+            tags: Tags::default(),
+            tag_rules: vec![],
+            pending: false,
+            pending_if_empty: false,
+            focus_mode: false,
+            focused: false,
+            generators: false,
+            forall: None,
+            returns: None,
+            timeout: None,
+            shared: None,
+            path: vec![],
+            descriptions: vec![],
         }
     }
 
-    pub fn new(before: &[syn::Stmt], after: &[syn::Stmt]) -> Self {
+    pub fn new(before: &[SectionItem], after: &[SectionItem]) -> Self {
         Self {
             before: Vec::from(before),
             inner: None,
             after: Vec::from(after),
+            attrs: vec![],
+            tags: Tags::default(),
+            tag_rules: vec![],
+            pending: false,
+            pending_if_empty: false,
+            focus_mode: false,
+            focused: false,
+            generators: false,
+            forall: None,
+            returns: None,
+            timeout: None,
+            shared: None,
+            path: vec![],
+            descriptions: vec![],
         }
     }
 
-    pub fn push_mut(&mut self, before: &[syn::Stmt], after: &[syn::Stmt]) {
+    pub fn push_mut(&mut self, before: &[SectionItem], after: &[SectionItem]) {
         if let Some(inner) = self.inner.as_mut() {
             inner.push_mut(before, after);
         } else {
@@ -33,33 +85,227 @@ impl Scope {
         }
     }
 
-    pub fn quote_with(&self, stmts: &[syn::Stmt]) -> TokenStream {
-        let Scope {
-            before,
-            inner,
-            after,
-        } = &self;
+    /// Attributes inherited from the enclosing sections, which should end
+    /// up on every test generated within this scope.
+    pub fn attrs(&self) -> &[syn::Attribute] {
+        &self.attrs
+    }
 
-        if let Some(inner) = inner.as_ref() {
-            let inner = inner.quote_with(stmts);
+    pub fn push_attrs(&mut self, attrs: &[syn::Attribute]) {
+        self.attrs.extend_from_slice(attrs);
+    }
 
-            quote! {
-                #(#before)*
-                {
-                    #inner
-                }
-                #(#after)*
-            }
-        } else {
-            quote! {
+    /// The tags of the enclosing sections.
+    pub fn tags(&self) -> &Tags {
+        &self.tags
+    }
+
+    pub fn push_tags(&mut self, tags: &Tags) {
+        self.tags.extend(tags);
+    }
+
+    /// The attributes added to the tests carrying a tag, as set at the top of
+    /// the `describe!` block.
+    pub fn tag_rules(&self) -> &[TagRule] {
+        &self.tag_rules
+    }
+
+    pub fn push_tag_rules(&mut self, rules: &[TagRule]) {
+        self.tag_rules.extend_from_slice(rules);
+    }
+
+    /// Whether any of the enclosing sections is pending, in which case the
+    /// tests within this scope are too.
+    pub fn pending(&self) -> bool {
+        self.pending
+    }
+
+    pub fn push_pending(&mut self) {
+        self.pending = true;
+    }
+
+    /// Whether sections with empty bodies are pending, as set at the top of
+    /// the `describe!` block.
+    pub fn pending_if_empty(&self) -> bool {
+        self.pending_if_empty
+    }
+
+    pub fn push_pending_if_empty(&mut self) {
+        self.pending_if_empty = true;
+    }
+
+    /// Whether any section of the `describe!` block is focused, in which case
+    /// only the tests within the focused sections run.
+    pub fn focus_mode(&self) -> bool {
+        self.focus_mode
+    }
+
+    pub fn push_focus_mode(&mut self) {
+        self.focus_mode = true;
+    }
+
+    /// Whether any of the enclosing sections is focused.
+    pub fn focused(&self) -> bool {
+        self.focused
+    }
+
+    pub fn push_focused(&mut self) {
+        self.focused = true;
+    }
+
+    /// Whether any of the enclosing sections contains a `generate!`, in
+    /// which case the tests have to be run for every generated value.
+    pub fn has_generators(&self) -> bool {
+        self.generators
+    }
+
+    pub fn push_generators(&mut self) {
+        self.generators = true;
+    }
+
+    /// The `forall` section the tests within this scope belong to, if any.
+    pub fn forall(&self) -> Option<&Forall> {
+        self.forall.as_ref()
+    }
+
+    pub fn push_forall(&mut self, forall: Forall) {
+        self.forall = Some(forall);
+    }
+
+    /// The return type of the tests within this scope, as declared by the
+    /// innermost section that has one.
+    pub fn returns(&self) -> Option<&Returns> {
+        self.returns.as_ref()
+    }
+
+    pub fn push_returns(&mut self, returns: Returns) {
+        self.returns = Some(returns);
+    }
+
+    /// The time limit of the tests within this scope, as set by the innermost
+    /// section that has one.
+    pub fn timeout(&self) -> Option<&Timeout> {
+        self.timeout.as_ref()
+    }
+
+    pub fn push_timeout(&mut self, timeout: Timeout) {
+        self.timeout = Some(timeout);
+    }
+
+    /// The path to the tests within this scope through the sections of the
+    /// enclosing `#[shared_setup]` section (if any), as the indices of the
+    /// nested sections to enter at every level.
+    pub fn shared(&self) -> Option<&[usize]> {
+        self.shared.as_deref()
+    }
+
+    pub fn push_shared(&mut self) {
+        self.shared = Some(vec![]);
+    }
+
+    pub fn push_branch(&mut self, idx: usize) {
+        if let Some(shared) = self.shared.as_mut() {
+            shared.push(idx);
+        }
+    }
+
+    /// The titles of the enclosing sections, used to point at a test in
+    /// failure messages, e.g. `given "x" / when "y" / then "z"`.
+    pub fn path(&self) -> String {
+        self.path.join(" / ")
+    }
+
+    /// The descriptions of the enclosing sections, prefixed with their
+    /// keywords, which the tests are filtered by at runtime.
+    pub fn descriptions(&self) -> &[String] {
+        &self.descriptions
+    }
+
+    pub fn push_path(&mut self, title: String, description: String) {
+        self.path.push(title);
+        self.descriptions.push(description);
+    }
+
+    /// Quotes the statements of the enclosing sections around the ones of a
+    /// test, every section in its own block.
+    pub fn quote_with(
+        &self,
+        stmts: &[SectionItem],
+        mode: &CatchrMode,
+    ) -> TokenStream {
+        self.quote_level(stmts, mode, self.returns.as_ref())
+    }
+
+    fn quote_level(
+        &self,
+        stmts: &[SectionItem],
+        mode: &CatchrMode,
+        returns: Option<&Returns>,
+    ) -> TokenStream {
+        let inner = match self.inner.as_ref() {
+            Some(inner) => inner.quote_level(stmts, mode, returns),
+            None => Self::quote_stmts(stmts, None, &[], mode, returns),
+        };
+
+        Self::quote_stmts(&self.before, Some(inner), &self.after, mode, returns)
+    }
+
+    /// Quotes the statements of a single section, with the block of the
+    /// nested section (if any) in between. Everything following a `defer`
+    /// is wrapped, so that the deferred block runs even if it panics.
+    fn quote_stmts(
+        before: &[SectionItem],
+        inner: Option<TokenStream>,
+        after: &[SectionItem],
+        mode: &CatchrMode,
+        returns: Option<&Returns>,
+    ) -> TokenStream {
+        if let Some((head, defer, tail)) = Self::split_at_defer(before) {
+            let head = head.iter().filter_map(SectionItem::stmt);
+            let rest = Self::quote_stmts(tail, inner, after, mode, returns);
+            let rest = defer.quote_around(rest, mode, returns);
+
+            return quote! {
+                #(#head)*
+                #rest
+            };
+        }
+
+        let before = before.iter().filter_map(SectionItem::stmt);
+        let inner = inner.map(|inner| quote!({ #inner }));
+
+        if let Some((head, defer, tail)) = Self::split_at_defer(after) {
+            let head = head.iter().filter_map(SectionItem::stmt);
+            let rest = Self::quote_stmts(tail, None, &[], mode, returns);
+            let rest = defer.quote_around(rest, mode, returns);
+
+            return quote! {
                 #(#before)*
-                {
-                    #(#stmts)*
-                }
-                #(#after)*
-            }
+                #inner
+                #(#head)*
+                #rest
+            };
+        }
+
+        let after = after.iter().filter_map(SectionItem::stmt);
+
+        quote! {
+            #(#before)*
+            #inner
+            #(#after)*
         }
     }
+
+    fn split_at_defer(
+        stmts: &[SectionItem],
+    ) -> Option<(&[SectionItem], &Defer, &[SectionItem])> {
+        stmts.iter().enumerate().find_map(|(idx, stmt)| match stmt {
+            SectionItem::Defer(defer) => {
+                Some((&stmts[..idx], defer, &stmts[idx + 1..]))
+            }
+            _ => None,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -68,6 +314,7 @@ mod tests {
     use syn::parse_quote;
 
     use super::*;
+    use crate::tokio_args::TokioArgs;
 
     fn assert_eq_string(exp: impl ToString, act: impl ToString) {
         assert_eq!(exp.to_string(), act.to_string());
@@ -77,7 +324,7 @@ mod tests {
     fn quote_empty_scope() {
         let scope = Scope::empty();
 
-        let act = scope.quote_with(&[]);
+        let act = scope.quote_with(&[], &CatchrMode::Regular);
 
         assert_eq_string(quote!({}), act);
     }
@@ -86,10 +333,13 @@ mod tests {
     fn quote_empty_scope_with_items() {
         let scope = Scope::empty();
 
-        let act = scope.quote_with(&[
-            parse_quote!(let x = 1;),
-            parse_quote!(assert_eq!(x, 1);),
-        ]);
+        let act = scope.quote_with(
+            &[
+                SectionItem::Stmt(parse_quote!(let x = 1;)),
+                SectionItem::Stmt(parse_quote!(assert_eq!(x, 1);)),
+            ],
+            &CatchrMode::Regular,
+        );
 
         assert_eq_string(
             quote!({
@@ -103,11 +353,11 @@ mod tests {
     #[test]
     fn quote_non_empty_scope() {
         let scope = Scope::new(
-            &[parse_quote!(let x = 1;)],
-            &[parse_quote!(assert_eq!(x, 1);)],
+            &[SectionItem::Stmt(parse_quote!(let x = 1;))],
+            &[SectionItem::Stmt(parse_quote!(assert_eq!(x, 1);))],
         );
 
-        let act = scope.quote_with(&[]);
+        let act = scope.quote_with(&[], &CatchrMode::Regular);
 
         assert_eq_string(
             quote!(
@@ -124,11 +374,14 @@ mod tests {
     #[test]
     fn quote_non_empty_scope_with_items() {
         let scope = Scope::new(
-            &[parse_quote!(let x = 1;)],
-            &[parse_quote!(assert_eq!(x, 1);)],
+            &[SectionItem::Stmt(parse_quote!(let x = 1;))],
+            &[SectionItem::Stmt(parse_quote!(assert_eq!(x, 1);))],
         );
 
-        let act = scope.quote_with(&[parse_quote!(assert!(true);)]);
+        let act = scope.quote_with(
+            &[SectionItem::Stmt(parse_quote!(assert!(true);))],
+            &CatchrMode::Regular,
+        );
 
         assert_eq_string(
             quote!(
@@ -147,7 +400,7 @@ mod tests {
         let mut scope = Scope::empty();
         scope.push_mut(&[], &[]);
 
-        let act = scope.quote_with(&[]);
+        let act = scope.quote_with(&[], &CatchrMode::Regular);
 
         assert_eq_string(quote!({ {} }), act);
     }
@@ -155,16 +408,19 @@ mod tests {
     #[test]
     fn push_non_empty_scope() {
         let mut scope = Scope::new(
-            &[parse_quote!(let x = 1;)],
-            &[parse_quote!(assert_eq!(x, 1);)],
+            &[SectionItem::Stmt(parse_quote!(let x = 1;))],
+            &[SectionItem::Stmt(parse_quote!(assert_eq!(x, 1);))],
         );
 
         scope.push_mut(
-            &[parse_quote!(assert!(true);)],
-            &[parse_quote!(assert!(false);)],
+            &[SectionItem::Stmt(parse_quote!(assert!(true);))],
+            &[SectionItem::Stmt(parse_quote!(assert!(false);))],
         );
 
-        let act = scope.quote_with(&[parse_quote!(assert!(true);)]);
+        let act = scope.quote_with(
+            &[SectionItem::Stmt(parse_quote!(assert!(true);))],
+            &CatchrMode::Regular,
+        );
 
         assert_eq_string(
             quote!(
@@ -181,4 +437,46 @@ mod tests {
             act,
         );
     }
+
+    #[test]
+    fn defer_after_nested_section() {
+        let scope = Scope::new(
+            &[SectionItem::Stmt(parse_quote!(let x = 1;))],
+            &[
+                SectionItem::Defer(Defer::new(parse_quote!({
+                    stop(x).await;
+                }))),
+                SectionItem::Stmt(parse_quote!(assert_eq!(x, 1);)),
+            ],
+        );
+
+        let act = scope.quote_with(
+            &[SectionItem::Stmt(parse_quote!(assert!(true);))],
+            &CatchrMode::Tokio(TokioArgs::default()),
+        );
+
+        assert_eq_string(
+            quote!(
+                let x = 1;
+                {
+                    assert!(true);
+                }
+                let __catchr_outcome =
+                    ::catchr::__private::catch_unwind_async(async {
+                        assert_eq!(x, 1);
+                    })
+                    .await;
+                let __catchr_cleanup =
+                    ::catchr::__private::catch_unwind_async(async {
+                        stop(x).await;
+                    })
+                    .await;
+                ::catchr::__private::resume_deferred(
+                    __catchr_outcome,
+                    __catchr_cleanup,
+                );
+            ),
+            act,
+        );
+    }
 }
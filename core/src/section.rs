@@ -1,19 +1,46 @@
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, ToTokens, TokenStreamExt};
 use syn::parse::{self, Parse, ParseStream};
-use utils::extract_literal_string;
+use syn::Token;
 
 use crate::catchr_mode::CatchrMode;
+use crate::dynamic_section::DynamicSection;
+use crate::examples::Examples;
+use crate::filter;
+use crate::focus;
+use crate::forall::Forall;
+use crate::generate::Generate;
+use crate::returns::Returns;
+use crate::runtime;
 use crate::scope::Scope;
 use crate::section_body::SectionBody;
 use crate::section_item::SectionItem;
 use crate::section_keyword::SectionKeyword;
+use crate::section_mark::SectionMark;
+use crate::shared;
+use crate::sibling_names::SiblingNames;
+use crate::tags::Tags;
+use crate::timeout::Timeout;
+use crate::tokio_args::TokioArgs;
 use crate::utils;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub struct Section {
+    attrs: Vec<syn::Attribute>,
+    is_async: bool,
     section_kind: SectionKeyword,
-    name: String,
+    keyword_span: Span,
+    name: syn::LitStr,
+    tags: Tags,
+    examples: Option<Examples>,
+    forall: Option<Forall>,
+    returns: Option<Returns>,
+    tokio_args: Option<TokioArgs>,
+    timeout: Option<Timeout>,
+    runtime: bool,
+    shared: bool,
+    pending: bool,
+    focused: bool,
     body: SectionBody,
 
     test_attribute: CatchrMode,
@@ -26,21 +53,228 @@ impl Section {
         body: SectionBody,
     ) -> Self {
         Self {
+            attrs: vec![],
+            is_async: false,
             section_kind,
-            name: name.to_string(),
+            keyword_span: Span::call_site(),
+            name: syn::LitStr::new(&name.to_string(), Span::call_site()),
+            tags: Tags::default(),
+            examples: None,
+            forall: None,
+            returns: None,
+            tokio_args: None,
+            timeout: None,
+            runtime: false,
+            shared: false,
+            pending: false,
+            focused: false,
             body,
             test_attribute: CatchrMode::Regular,
         }
     }
 
     pub fn with_mode(mut self, test_attribute: CatchrMode) -> Self {
+        let test_attribute = match (test_attribute, &self.tokio_args) {
+            (CatchrMode::Tokio(args), Some(own)) => {
+                CatchrMode::Tokio(args.merge(own))
+            }
+            (test_attribute, _) => test_attribute,
+        };
+
+        self.body = self.body.with_mode(test_attribute.clone());
         self.test_attribute = test_attribute;
-        self.body = self.body.with_mode(test_attribute);
         self
     }
 
-    fn quote_name(&self) -> Ident {
-        let name = utils::escape_name(&self.name);
+    pub fn with_attrs(mut self, attrs: Vec<syn::Attribute>) -> Self {
+        self.attrs = attrs;
+        self
+    }
+
+    pub fn attrs(&self) -> &[syn::Attribute] {
+        &self.attrs
+    }
+
+    /// Makes the tests in this section async, even if the enclosing ones
+    /// aren't.
+    pub fn with_async(mut self, is_async: bool) -> Self {
+        self.is_async = is_async;
+        self
+    }
+
+    pub fn is_async(&self) -> bool {
+        self.is_async
+    }
+
+    /// Tags the section, and all of the tests within it.
+    pub fn with_tags(mut self, tags: Tags) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    pub fn tags(&self) -> &Tags {
+        &self.tags
+    }
+
+    pub fn with_examples(mut self, examples: Examples) -> Self {
+        self.examples = Some(examples);
+        self
+    }
+
+    pub fn examples(&self) -> Option<&Examples> {
+        self.examples.as_ref()
+    }
+
+    pub fn with_forall(mut self, forall: Forall) -> Self {
+        self.forall = Some(forall);
+        self
+    }
+
+    pub fn forall(&self) -> Option<&Forall> {
+        self.forall.as_ref()
+    }
+
+    pub fn with_returns(mut self, returns: Returns) -> Self {
+        self.returns = Some(returns);
+        self
+    }
+
+    pub fn returns(&self) -> Option<&Returns> {
+        self.returns.as_ref()
+    }
+
+    /// Overrides the configuration of the tokio runtime for the tests in
+    /// this section.
+    pub fn with_tokio_args(mut self, tokio_args: TokioArgs) -> Self {
+        self.tokio_args = Some(tokio_args);
+        self
+    }
+
+    pub fn tokio_args(&self) -> Option<&TokioArgs> {
+        self.tokio_args.as_ref()
+    }
+
+    pub fn with_timeout(mut self, timeout: Timeout) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn timeout(&self) -> Option<&Timeout> {
+        self.timeout.as_ref()
+    }
+
+    /// Turns this section into a single test, whose nested sections are
+    /// picked at runtime - see `runtime::is_runtime_attr`.
+    pub fn with_runtime(mut self, runtime: bool) -> Self {
+        self.runtime = runtime;
+        self
+    }
+
+    pub fn is_runtime(&self) -> bool {
+        self.runtime
+    }
+
+    /// Generates the statements of this section and its nested sections only
+    /// once - see `shared::is_shared_attr`.
+    pub fn with_shared(mut self, shared: bool) -> Self {
+        self.shared = shared;
+        self
+    }
+
+    pub fn is_shared(&self) -> bool {
+        self.shared
+    }
+
+    /// Marks this section and all of the sections within it as not
+    /// implemented yet, like `xthen "..." { ... }`. Their tests are ignored,
+    /// with empty bodies, so the code within them doesn't have to compile.
+    pub fn with_pending(mut self, pending: bool) -> Self {
+        self.pending = pending;
+        self
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    /// Focuses this section, like `fthen "..." { ... }` or `#[focus]` - the
+    /// tests of the `describe!` block outside of the focused sections are
+    /// ignored.
+    pub fn with_focused(mut self, focused: bool) -> Self {
+        self.focused = focused;
+        self
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Whether this section, or any of the sections within it, is focused.
+    pub fn has_focus(&self) -> bool {
+        self.focused
+            || self.body.items().iter().any(|item| match item {
+                SectionItem::Sep(section) => section.has_focus(),
+                _ => false,
+            })
+    }
+
+    pub fn section_kind(&self) -> &SectionKeyword {
+        &self.section_kind
+    }
+
+    pub fn keyword_span(&self) -> Span {
+        self.keyword_span
+    }
+
+    pub fn name(&self) -> String {
+        self.name.value()
+    }
+
+    pub fn name_span(&self) -> Span {
+        self.name.span()
+    }
+
+    /// The keyword and the description, as written in the source, e.g.
+    /// `then "x should equal 2"`.
+    pub fn title(&self) -> String {
+        format!("{} {:?}", self.section_kind.to_name(), self.name())
+    }
+
+    /// The keyword and the description, unquoted - what the tests are
+    /// filtered by at runtime, e.g. `then x should equal 2`.
+    pub fn description(&self) -> String {
+        format!("{} {}", self.section_kind.to_name(), self.name())
+    }
+
+    pub fn body(&self) -> &SectionBody {
+        &self.body
+    }
+
+    /// The number of tests generated for this section - one for every leaf
+    /// and every row of the examples above it. Runtime sections are a single
+    /// test.
+    pub fn count_tests(&self) -> usize {
+        let rows = self.examples.as_ref().map_or(1, |ex| ex.rows().len());
+
+        if self.runtime || self.body.is_top_level() {
+            return rows;
+        }
+
+        let leaves: usize = self
+            .body
+            .items()
+            .iter()
+            .filter_map(|item| match item {
+                SectionItem::Sep(section) => Some(section.count_tests()),
+                _ => None,
+            })
+            .sum();
+
+        leaves * rows
+    }
+
+    pub(crate) fn quote_name(&self) -> Ident {
+        let name = utils::escape_name(self.name());
         let kind = self.section_kind.to_name();
 
         let name = if kind.is_empty() {
@@ -49,7 +283,7 @@ impl Section {
             format!("{}_{}", kind, name)
         };
 
-        Ident::new(&name, Span::call_site())
+        Ident::new(&name, self.name.span())
     }
 
     pub fn quote_inner(&self, scope: Scope) -> TokenStream {
@@ -60,95 +294,2350 @@ impl Section {
         token_stream
     }
 
+    /// Checks whether a section starts here. A keyword on its own isn't
+    /// enough, since it might just as well be a variable or a function called
+    /// `case` or `when` - only a keyword followed by a literal is a section.
+    /// No statement can start that way, so it's parsed as a section even if
+    /// the literal isn't a description, or the body is missing, to point at
+    /// what's wrong with it.
     pub fn peek(input: ParseStream) -> bool {
-        SectionKeyword::peek(input)
+        let fork = input.fork();
+
+        if fork.call(syn::Attribute::parse_outer).is_err() {
+            return false;
+        }
+
+        if fork.parse::<Option<Token![async]>>().is_err() {
+            return false;
+        }
+
+        SectionKeyword::parse_marked(&fork).is_ok() && fork.peek(syn::Lit)
     }
 
-    fn to_tokens_inner(&self, scope: Scope, tokens: &mut TokenStream) {
-        if self.body.is_top_level() {
-            let my_stmts: Vec<_> =
-                self.body.items().iter().filter_map(|i| i.stmt()).collect();
+    /// Attributes that only make sense on test functions (like `#[ignore]`)
+    /// can't be put on a module, so instead they're pushed down to every
+    /// test in the subtree.
+    fn is_inherited_attr(attr: &syn::Attribute) -> bool {
+        attr.path.is_ident("ignore") || attr.path.is_ident("should_panic")
+    }
 
-            let name = self.quote_name();
+    /// Attributes of a generated test - its own ones, followed by the ones
+    /// inherited from the enclosing sections. The innermost attribute with a
+    /// given path wins. The attributes of its tags come last.
+    fn test_attrs(&self, scope: &Scope) -> Vec<syn::Attribute> {
+        let mut attrs = self.own_attrs(scope);
 
-            let inner = scope.quote_with(&my_stmts);
+        for attr in scope.attrs().iter().rev() {
+            if !attrs.iter().any(|own| own.path == attr.path) {
+                attrs.push(attr.clone());
+            }
+        }
 
-            match self.test_attribute {
-                CatchrMode::Regular => tokens.append_all(quote! {
-                    #[test]
-                    fn #name() {
-                        #inner
-                    }
-                }),
-                CatchrMode::Tokio => tokens.append_all(quote! {
-                    #[tokio::test]
-                    async fn #name() {
-                        #inner
-                    }
-                }),
+        // The tests of runtime sections run their nested sections too, so
+        // they count as focused if any of those is
+        let focused = scope.focused() || self.has_focus();
+        let ignored = attrs.iter().any(|attr| attr.path.is_ident("ignore"));
+
+        if scope.focus_mode() && !focused && !ignored {
+            attrs.push(syn::parse_quote!(#[ignore = "not focused"]));
+        }
+
+        let tags = self.test_tags(scope);
+
+        for rule in scope.tag_rules() {
+            if tags.contains(rule.tag()) && !attrs.contains(rule.attr()) {
+                attrs.push(rule.attr().clone());
+            }
+        }
+
+        attrs
+    }
+
+    /// The attributes of this section, with the `#[ignore]` of a pending
+    /// section added - unless it has one of its own.
+    fn own_attrs(&self, scope: &Scope) -> Vec<syn::Attribute> {
+        let mut attrs = self.attrs.clone();
+
+        let pending = self.pending
+            || (scope.pending_if_empty() && self.body.items().is_empty());
+
+        if pending && !attrs.iter().any(|attr| attr.path.is_ident("ignore")) {
+            let reason = format!("pending: {}", self.name());
+            attrs.push(syn::parse_quote!(#[ignore = #reason]));
+        }
+
+        attrs
+    }
+
+    /// Whether the tests of this section are pending - see `with_pending`.
+    fn is_pending_in(&self, scope: &Scope) -> bool {
+        self.pending
+            || scope.pending()
+            || (scope.pending_if_empty() && self.body.items().is_empty())
+    }
+
+    /// Quotes a test of a pending section - ignored, and with an empty body.
+    fn pending_to_tokens(&self, scope: &Scope, tokens: &mut TokenStream) {
+        tokens.append_all(self.test_attribute.quote_test(
+            &self.quote_name(),
+            &self.test_attrs(scope),
+            TokenStream::new(),
+            TokenStream::new(),
+            TokenStream::new(),
+            scope,
+        ));
+    }
+
+    /// Tags of a generated test - its own ones, and the ones of the enclosing
+    /// sections.
+    fn test_tags(&self, scope: &Scope) -> Tags {
+        let mut tags = scope.tags().clone();
+        tags.extend(&self.tags);
+
+        tags
+    }
+
+    /// Pushes the attributes (and tags) that should be inherited by the tests
+    /// in the subtree into the scope, returning the ones meant for the module.
+    fn push_attrs(&self, scope: &mut Scope) -> Vec<syn::Attribute> {
+        let (inherited, attrs): (Vec<_>, Vec<_>) = self
+            .own_attrs(scope)
+            .into_iter()
+            .partition(Self::is_inherited_attr);
+
+        scope.push_attrs(&inherited);
+        scope.push_tags(&self.tags);
+
+        if self.pending {
+            scope.push_pending();
+        }
+
+        if self.focused {
+            scope.push_focused();
+        }
+
+        attrs
+    }
+
+    /// The error reported for a test that uses `.await`, but isn't async.
+    fn await_error(&self, scope: &Scope) -> parse::Error {
+        let title = self.title();
+
+        let hint = match scope.forall() {
+            Some(_) => "tests in forall sections can't be async".to_string(),
+            None => format!(
+                "it isn't async - mark it (or an enclosing section) as `async {}`",
+                title,
+            ),
+        };
+
+        parse::Error::new(
+            self.keyword_span,
+            format!("`{}` uses `.await`, but {}", title, hint),
+        )
+    }
+
+    /// The error reported for a dynamic section that isn't within a runtime
+    /// section (or is in a `defer` block of one).
+    fn dynamic_error(span: Span) -> parse::Error {
+        parse::Error::new(
+            span,
+            "`dynamic_section!` can only be used within sections marked with \
+             `#[runtime_sections]`",
+        )
+    }
+
+    /// Expands every row of the example table into its own copy of the
+    /// section, with the values bound at the top of its scope.
+    fn examples_to_tokens(
+        &self,
+        examples: &Examples,
+        mut scope: Scope,
+        tokens: &mut TokenStream,
+    ) {
+        let attrs = self.push_attrs(&mut scope);
+
+        let stream = examples.rows().iter().enumerate().map(|(idx, row)| {
+            let name = examples.interpolate(&self.name(), row);
+
+            let section = Section {
+                attrs: vec![],
+                name: syn::LitStr::new(
+                    &format!("{} {}", idx, name),
+                    self.name.span(),
+                ),
+                examples: None,
+                tokio_args: None,
+                ..self.clone()
+            };
+
+            // Within a shared setup, the values are bound in the shared
+            // function instead
+            let mut scope = scope.clone();
+
+            match scope.shared() {
+                Some(_) => scope.push_branch(idx),
+                None => scope
+                    .push_mut(&[SectionItem::Stmt(examples.binding(row))], &[]),
+            }
+
+            section.quote_inner(scope)
+        });
+
+        let name = self.quote_name();
+
+        tokens.append_all(quote! {
+            #(#attrs)*
+            mod #name {
+                use super::*;
+
+                #(#stream)*
+            }
+        });
+    }
+
+    /// Quotes a section with runtime sections - a single test, which runs
+    /// its body until every nested section has been entered.
+    fn runtime_to_tokens(&self, scope: &Scope, tokens: &mut TokenStream) {
+        if scope.forall().is_some() || scope.has_generators() {
+            tokens.append_all(
+                parse::Error::new(
+                    self.keyword_span,
+                    "runtime sections can't be used together with forall \
+                     sections or generators",
+                )
+                .to_compile_error(),
+            );
+            return;
+        }
+
+        let items = match self.runtime_items(scope) {
+            Ok(items) => items,
+            Err(err) => {
+                tokens.append_all(err.to_compile_error());
+                return;
+            }
+        };
+
+        let name = self.quote_name();
+        let attrs = self.test_attrs(scope);
+        let inner = scope.quote_with(&items, &self.test_attribute);
+
+        if let Some(span) = DynamicSection::find(inner.clone()) {
+            tokens.append_all(Self::dynamic_error(span).to_compile_error());
+            return;
+        }
+
+        if !self.test_attribute.is_async()
+            && utils::find_await(inner.clone()).is_some()
+        {
+            tokens.append_all(self.await_error(scope).to_compile_error());
+            return;
+        }
+
+        let inner = runtime::quote_passes(
+            inner,
+            scope.returns(),
+            &self.test_attribute,
+            &scope.path(),
+        );
+
+        let skip = filter::quote_skip(
+            scope.descriptions(),
+            &self.test_tags(scope),
+            &attrs,
+            false,
+            true,
+        );
+
+        tokens.append_all(self.test_attribute.quote_test(
+            &name,
+            &attrs,
+            TokenStream::new(),
+            skip,
+            inner,
+            scope,
+        ));
+    }
+
+    /// The items of a section with runtime sections, with the nested
+    /// sections turned into blocks entered when the tracker picks them.
+    fn runtime_items(&self, scope: &Scope) -> parse::Result<Vec<SectionItem>> {
+        let mut items = vec![];
+
+        // The tracker tells the sections apart by their titles, so siblings
+        // have to be unique here too
+        let mut names = SiblingNames::default();
+
+        for item in self.body.items() {
+            match item {
+                SectionItem::Sep(section) => {
+                    names.insert(section)?;
+                    items.extend(section.runtime_sections(scope)?)
+                }
+                SectionItem::Generate(_) => {
+                    return Err(parse::Error::new(
+                        self.keyword_span,
+                        "runtime sections can't be used together with \
+                         generators",
+                    ))
+                }
+                SectionItem::Stmt(stmt) => {
+                    items.push(SectionItem::Stmt(DynamicSection::lower_stmt(
+                        stmt,
+                        scope,
+                        &self.test_attribute,
+                    )?))
+                }
+                item => items.push(item.clone()),
+            }
+        }
+
+        Ok(items)
+    }
+
+    /// A nested section of a section with runtime sections, as a block for
+    /// each of its examples.
+    fn runtime_sections(
+        &self,
+        scope: &Scope,
+    ) -> parse::Result<Vec<SectionItem>> {
+        // Pending sections are never entered
+        if self.pending {
+            return Ok(vec![]);
+        }
+
+        let unsupported = if !self.attrs.is_empty()
+            || self.tokio_args.is_some()
+            || self.timeout.is_some()
+            || self.runtime
+        {
+            Some("attributes")
+        } else if self.is_async {
+            Some("`async`")
+        } else if self.forall.is_some() {
+            Some("`forall`")
+        } else if self.returns.is_some() {
+            Some("return types")
+        } else {
+            None
+        };
+
+        if let Some(unsupported) = unsupported {
+            return Err(parse::Error::new(
+                self.keyword_span,
+                format!(
+                    "{} can't be used on sections nested in runtime sections",
+                    unsupported
+                ),
+            ));
+        }
+
+        if let Some(examples) = &self.examples {
+            let mut items = vec![];
+
+            for (idx, row) in examples.rows().iter().enumerate() {
+                let name = examples.interpolate(&self.name(), row);
+
+                let mut body = vec![SectionItem::Stmt(examples.binding(row))];
+                body.extend(self.body.items().iter().cloned());
+
+                let section = Section {
+                    name: syn::LitStr::new(
+                        &format!("{} {}", idx, name),
+                        self.name.span(),
+                    ),
+                    examples: None,
+                    body: SectionBody::new(body),
+                    ..self.clone()
+                };
+
+                items.extend(section.runtime_sections(scope)?);
+            }
+
+            return Ok(items);
+        }
+
+        let title = self.title();
+
+        Ok(vec![SectionItem::Stmt(runtime::quote_enter(
+            quote!(#title),
+            self.quote_runtime_body(scope)?,
+        ))])
+    }
+
+    /// The body of a section nested in a runtime section.
+    pub(crate) fn quote_runtime_body(
+        &self,
+        scope: &Scope,
+    ) -> parse::Result<TokenStream> {
+        let mut inner_scope = Scope::empty();
+
+        if let Some(returns) = scope.returns() {
+            inner_scope.push_returns(returns.clone());
+        }
+
+        Ok(inner_scope
+            .quote_with(&self.runtime_items(scope)?, &self.test_attribute))
+    }
+
+    /// The function of a `#[shared_setup]` section, with the statements of
+    /// the section and all of its nested sections, running the path through
+    /// them given by a test.
+    fn shared_run_fn(&self, scope: &Scope) -> parse::Result<TokenStream> {
+        if scope.shared().is_some() {
+            return Err(parse::Error::new(
+                self.keyword_span,
+                "`#[shared_setup]` sections can't be nested",
+            ));
+        }
+
+        if scope.forall().is_some() || scope.has_generators() {
+            return Err(parse::Error::new(
+                self.keyword_span,
+                "`#[shared_setup]` can't be used together with forall \
+                 sections or generators",
+            ));
+        }
+
+        let inner = scope
+            .quote_with(&self.shared_items(scope, 0)?, &self.test_attribute);
+
+        if let Some(span) = DynamicSection::find(inner.clone()) {
+            return Err(Self::dynamic_error(span));
+        }
+
+        if !self.test_attribute.is_async()
+            && utils::find_await(inner.clone()).is_some()
+        {
+            return Err(self.await_error(scope));
+        }
+
+        Ok(shared::quote_run_fn(
+            inner,
+            scope.returns(),
+            &self.test_attribute,
+        ))
+    }
+
+    /// The items of a section within a shared setup, with the nested
+    /// sections turned into blocks entered by the tests going through them.
+    fn shared_items(
+        &self,
+        scope: &Scope,
+        depth: usize,
+    ) -> parse::Result<Vec<SectionItem>> {
+        let mut items = vec![];
+        let mut branch = 0;
+
+        for item in self.body.items() {
+            match item {
+                // The tests of pending sections don't run the shared function
+                SectionItem::Sep(section) if section.pending => branch += 1,
+                SectionItem::Sep(section) => {
+                    items.push(SectionItem::Stmt(
+                        section.shared_branch(scope, depth, branch)?,
+                    ));
+                    branch += 1;
+                }
+                SectionItem::Generate(_) => {
+                    return Err(parse::Error::new(
+                        self.keyword_span,
+                        "`#[shared_setup]` can't be used together with forall \
+                         sections or generators",
+                    ))
+                }
+                item => items.push(item.clone()),
+            }
+        }
+
+        Ok(items)
+    }
+
+    /// A nested section of a `#[shared_setup]` section, as a block entered
+    /// when it's on the path of the test.
+    fn shared_branch(
+        &self,
+        scope: &Scope,
+        depth: usize,
+        idx: usize,
+    ) -> parse::Result<syn::Stmt> {
+        let unsupported = if self.runtime || self.shared {
+            Some("`#[runtime_sections]` and `#[shared_setup]`")
+        } else if self.is_async {
+            Some("`async`")
+        } else if self.forall.is_some() {
+            Some("`forall`")
+        } else if self.returns.is_some() {
+            Some("return types")
+        } else {
+            None
+        };
+
+        if let Some(unsupported) = unsupported {
+            return Err(parse::Error::new(
+                self.keyword_span,
+                format!(
+                    "{} can't be used on sections nested in `#[shared_setup]` \
+                     sections",
+                    unsupported
+                ),
+            ));
+        }
+
+        let mut inner_scope = Scope::empty();
+
+        if let Some(returns) = scope.returns() {
+            inner_scope.push_returns(returns.clone());
+        }
+
+        let inner = match &self.examples {
+            Some(examples) => {
+                let mut rows = vec![];
+
+                for (row_idx, row) in examples.rows().iter().enumerate() {
+                    let mut items =
+                        vec![SectionItem::Stmt(examples.binding(row))];
+                    items.extend(self.shared_items(scope, depth + 2)?);
+
+                    let inner =
+                        inner_scope.quote_with(&items, &self.test_attribute);
+
+                    rows.push(shared::quote_branch(
+                        depth + 1,
+                        row_idx,
+                        &[],
+                        inner,
+                    ));
+                }
+
+                quote!(#(#rows)*)
             }
+            None => inner_scope.quote_with(
+                &self.shared_items(scope, depth + 1)?,
+                &self.test_attribute,
+            ),
+        };
+
+        Ok(shared::quote_branch(depth, idx, &self.attrs, inner))
+    }
+
+    fn to_tokens_inner(&self, mut scope: Scope, tokens: &mut TokenStream) {
+        if self.is_async && !self.test_attribute.is_async() {
+            let mode = self.test_attribute.to_async();
+            let section = Section {
+                is_async: false,
+                ..self.clone()
+            };
+
+            section.with_mode(mode).to_tokens_inner(scope, tokens);
+            return;
+        }
+
+        if self.tokio_args.is_some()
+            && !matches!(self.test_attribute, CatchrMode::Tokio(_))
+        {
+            tokens.append_all(
+                parse::Error::new(
+                    self.keyword_span,
+                    "`#[tokio(...)]` can only be used on tests run by tokio",
+                )
+                .to_compile_error(),
+            );
+            return;
+        }
 
+        if let Some(examples) = &self.examples {
+            self.examples_to_tokens(examples, scope, tokens);
             return;
         }
 
-        let mut stream = vec![];
+        scope.push_path(self.title(), self.description());
+
+        if let Some(forall) = &self.forall {
+            if scope.forall().is_some() {
+                tokens.append_all(
+                    parse::Error::new(
+                        self.keyword_span,
+                        "forall sections can't be nested",
+                    )
+                    .to_compile_error(),
+                );
+                return;
+            }
+
+            scope.push_forall(forall.clone());
+            scope.push_mut(&[SectionItem::Stmt(forall.binding())], &[]);
+        }
+
+        if let Some(returns) = &self.returns {
+            scope.push_returns(returns.clone());
+        }
+
+        if let Some(timeout) = &self.timeout {
+            scope.push_timeout(timeout.clone());
+        }
+
+        if self.shared && self.body.is_top_level() {
+            let err = parse::Error::new(
+                self.keyword_span,
+                "`#[shared_setup]` can only be used on sections with nested \
+                 sections",
+            );
+
+            tokens.append_all(err.to_compile_error());
+            return;
+        }
+
+        if self.is_pending_in(&scope)
+            && (self.runtime || self.body.is_top_level())
+        {
+            self.pending_to_tokens(&scope, tokens);
+            return;
+        }
+
+        if self.runtime {
+            self.runtime_to_tokens(&scope, tokens);
+            return;
+        }
+
+        let run_fn = if self.shared && !self.is_pending_in(&scope) {
+            match self.shared_run_fn(&scope) {
+                Ok(run_fn) => {
+                    scope.push_shared();
+                    Some(run_fn)
+                }
+                Err(err) => {
+                    tokens.append_all(err.to_compile_error());
+                    return;
+                }
+            }
+        } else {
+            None
+        };
+
+        if self.body.is_top_level() {
+            let name = self.quote_name();
+            let attrs = self.test_attrs(&scope);
+
+            // Property-based tests are always run synchronously by proptest
+            let test_attribute = match scope.forall() {
+                Some(_) => CatchrMode::Regular,
+                None => self.test_attribute.clone(),
+            };
+
+            let inner = match scope.shared() {
+                Some(path) => {
+                    shared::quote_call(path, scope.returns(), &test_attribute)
+                }
+                None => {
+                    scope.quote_with(&self.body.get_stmts(), &test_attribute)
+                }
+            };
+
+            if let Some(span) = DynamicSection::find(inner.clone()) {
+                tokens.append_all(Self::dynamic_error(span).to_compile_error());
+                return;
+            }
+
+            if !test_attribute.is_async()
+                && utils::find_await(inner.clone()).is_some()
+            {
+                tokens.append_all(self.await_error(&scope).to_compile_error());
+                return;
+            }
+
+            // Tests with `#[should_panic]` have to return `()`
+            let should_panic =
+                attrs.iter().any(|attr| attr.path.is_ident("should_panic"));
+
+            let inner = match (scope.returns(), scope.forall()) {
+                (Some(returns), Some(_)) => returns.quote_property_run(inner),
+                (Some(returns), None) if should_panic => returns
+                    .quote_panicking_run(inner, &test_attribute, &scope.path()),
+                (Some(returns), None) => {
+                    returns.quote_run(inner, &test_attribute, &scope.path())
+                }
+                (None, _) => inner,
+            };
+
+            let inner = if scope.has_generators() || self.body.has_generators()
+            {
+                Generate::quote_runs(inner)
+            } else {
+                inner
+            };
+
+            let inner = match scope.forall() {
+                Some(forall) => forall.quote_runs(inner, &scope.path()),
+                None => inner,
+            };
+
+            // Errors of property-based tests are reported by proptest, so
+            // only the other tests return them
+            let (output, inner) = match (scope.returns(), scope.forall()) {
+                (Some(returns), None) if !should_panic => {
+                    let ty = returns.ty();
+
+                    (
+                        quote!(-> #ty),
+                        quote! {
+                            #inner
+
+                            ::std::result::Result::Ok(())
+                        },
+                    )
+                }
+                _ => (TokenStream::new(), inner),
+            };
+
+            let skip = filter::quote_skip(
+                scope.descriptions(),
+                &self.test_tags(&scope),
+                &attrs,
+                !output.is_empty(),
+                false,
+            );
+
+            tokens.append_all(
+                test_attribute
+                    .quote_test(&name, &attrs, output, skip, inner, &scope),
+            );
+
+            return;
+        }
+
+        let attrs = self.push_attrs(&mut scope);
+
+        if self.body.has_generators() {
+            scope.push_generators();
+        }
+
+        let mut stream = vec![];
+        stream.extend(run_fn);
+
+        let mut names = SiblingNames::default();
+        let mut branch = 0;
+
+        for (idx, item) in self.body.items().iter().enumerate() {
+            if let SectionItem::Sep(section) = item {
+                let mut scope = scope.clone();
+                scope.push_branch(branch);
+                branch += 1;
+
+                if let Err(err) = names.insert(section) {
+                    stream.push(err.to_compile_error());
+                    continue;
+                }
+
+                // Within a shared setup, the statements are only generated
+                // in the shared function
+                if scope.shared().is_none() {
+                    let sb = self.body.get_stmts_before(idx);
+                    let sa = self.body.get_stmts_after(idx);
+
+                    scope.push_mut(&sb, &sa);
+                }
+
+                let inner = section.quote_inner(scope);
+
+                stream.push(inner);
+            }
+        }
+
+        let name = self.quote_name();
+
+        tokens.append_all(quote! {
+            #(#attrs)*
+            mod #name {
+                use super::*;
+
+                #(#stream)*
+            }
+        });
+    }
+}
+
+// Spans are left out, so that parsed sections can be compared with the ones
+// created by hand.
+impl PartialEq for Section {
+    fn eq(&self, other: &Self) -> bool {
+        let Section {
+            attrs,
+            is_async,
+            section_kind,
+            keyword_span: _,
+            name,
+            tags,
+            examples,
+            forall,
+            returns,
+            tokio_args,
+            timeout,
+            runtime,
+            shared,
+            pending,
+            focused,
+            body,
+            test_attribute,
+        } = self;
+
+        *attrs == other.attrs
+            && *is_async == other.is_async
+            && *section_kind == other.section_kind
+            && *name == other.name
+            && *tags == other.tags
+            && *examples == other.examples
+            && *forall == other.forall
+            && *returns == other.returns
+            && *tokio_args == other.tokio_args
+            && *timeout == other.timeout
+            && *runtime == other.runtime
+            && *shared == other.shared
+            && *pending == other.pending
+            && *focused == other.focused
+            && *body == other.body
+            && *test_attribute == other.test_attribute
+    }
+}
+
+impl Eq for Section {}
+
+impl ToTokens for Section {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let scope = Scope::empty();
+
+        self.to_tokens_inner(scope, tokens);
+    }
+}
+
+impl Parse for Section {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let (tokio_attrs, attrs): (Vec<_>, Vec<_>) = input
+            .call(syn::Attribute::parse_outer)?
+            .into_iter()
+            .partition(TokioArgs::is_tokio_attr);
+
+        let tokio_args = tokio_attrs.iter().try_fold(
+            None,
+            |args: Option<TokioArgs>, attr| {
+                let own = TokioArgs::from_attr(attr)?;
+
+                parse::Result::Ok(Some(match args {
+                    Some(args) => args.merge(&own),
+                    None => own,
+                }))
+            },
+        )?;
+
+        let (timeout_attrs, attrs): (Vec<_>, Vec<_>) =
+            attrs.into_iter().partition(Timeout::is_timeout_attr);
+
+        // If a section is given multiple timeouts, the last one wins
+        let timeout = timeout_attrs
+            .iter()
+            .map(Timeout::from_attr)
+            .collect::<parse::Result<Vec<_>>>()?
+            .pop();
+
+        let (runtime_attrs, attrs): (Vec<_>, Vec<_>) =
+            attrs.into_iter().partition(runtime::is_runtime_attr);
+
+        let (shared_attrs, attrs): (Vec<_>, Vec<_>) =
+            attrs.into_iter().partition(shared::is_shared_attr);
+
+        let (focus_attrs, attrs): (Vec<_>, Vec<_>) =
+            attrs.into_iter().partition(focus::is_focus_attr);
+
+        if let Some(attr) = focus_attrs.iter().find(|a| !a.tokens.is_empty()) {
+            return Err(parse::Error::new_spanned(
+                &attr.tokens,
+                "`#[focus]` doesn't take any arguments",
+            ));
+        }
+
+        let is_async = input.parse::<Option<Token![async]>>()?.is_some();
+        let keyword_span = input.span();
+        let (section_keyword, mark) = SectionKeyword::parse_marked(input)?;
+        let name = match input.parse()? {
+            syn::Lit::Str(name) => name,
+            lit => {
+                return Err(parse::Error::new(
+                    lit.span(),
+                    "Invalid section literal",
+                ))
+            }
+        };
+
+        // Tags can be written either at the end of the description, or in
+        // brackets after it
+        let (description, mut tags) = Tags::from_description(&name.value());
+        let name = syn::LitStr::new(&description, name.span());
+
+        if Tags::peek(input) {
+            tags.extend(&input.parse()?);
+        }
+
+        let examples = if Examples::peek(input) {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
+        let forall = if section_keyword == SectionKeyword::Forall {
+            if !cfg!(feature = "proptest") {
+                return Err(parse::Error::new(
+                    keyword_span,
+                    "forall sections require the `proptest` feature of catchr",
+                ));
+            }
+
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
+        let returns = if Returns::peek(input) {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
+        let content;
+        syn::braced!(content in input);
+        let inner_body = content.parse::<SectionBody>()?;
+
+        Ok(Section {
+            attrs,
+            is_async,
+            section_kind: section_keyword,
+            keyword_span,
+            name,
+            tags,
+            examples,
+            forall,
+            returns,
+            tokio_args,
+            timeout,
+            runtime: !runtime_attrs.is_empty(),
+            shared: !shared_attrs.is_empty(),
+            pending: mark == Some(SectionMark::Pending),
+            focused: mark == Some(SectionMark::Focused)
+                || !focus_attrs.is_empty(),
+            body: inner_body,
+            test_attribute: CatchrMode::Regular,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    mod regular {
+        use test_case::test_case;
+
+        use super::*;
+
+        #[test_case(
+            r#"
+                section "tests" {
+                    let x = 1;
+                    when "hello" {
+                        assert!(true);
+                        then "whatever" {
+                            assert!(true);
+                        }
+                    }
+
+                    assert_eq!(x, 1);
+                }
+            "#,
+            quote!(
+                mod section_tests {
+                    use super::*;
+
+                    mod when_hello {
+                        use super::*;
+
+                        #[test]
+                        fn then_whatever() {
+                            if ::catchr::__private::skip(&["section tests", "when hello", "then whatever"], &[], false) {
+                                return;
+                            }
+
+                            {
+                                let x = 1;
+                                {
+                                    assert!(true);
+                                    {
+                                        assert!(true);
+                                    }
+                                }
+                                assert_eq!(x, 1);
+                            }
+                        }
+                    }
+                }
+            )
+        )]
+        #[test_case(
+            r#"
+                section "tests" {
+                    assert!(1 == 1);
+
+                    case "one" {
+                        assert!(2 == 2);
+                    }
+
+                    assert!(3 == 3);
+
+                    case "two" {
+                        assert!(4 == 4);
+                    }
+
+                    assert!(5 == 5);
+                }
+            "#,
+            quote!(
+                mod section_tests {
+                    use super::*;
+
+                    #[test]
+                    fn case_one() {
+                        if ::catchr::__private::skip(&["section tests", "case one"], &[], false) {
+                            return;
+                        }
+
+                        {
+                            assert!(1 == 1);
+                            {
+                                assert!(2 == 2);
+                            }
+                            assert!(3 == 3);
+                            assert!(5 == 5);
+                        }
+                    }
+
+                    #[test]
+                    fn case_two() {
+                        if ::catchr::__private::skip(&["section tests", "case two"], &[], false) {
+                            return;
+                        }
+
+                        {
+                            assert!(1 == 1);
+                            assert!(3 == 3);
+                            {
+                                assert!(4 == 4);
+                            }
+                            assert!(5 == 5);
+                        }
+                    }
+                }
+            )
+        )]
+        #[test_case(
+            r#"
+                section "tests" {
+                    #[ignore]
+                    #[cfg(feature = "db")]
+                    given "a database" {
+                        #[allow(unused_variables)]
+                        when "one" {
+                            let x = 1;
+                        }
+
+                        #[should_panic(expected = "boom")]
+                        then "panics" {
+                            panic!("boom");
+                        }
+
+                        #[ignore = "flaky"]
+                        then "flakes" {
+                            assert!(true);
+                        }
+                    }
+                }
+            "#,
+            quote!(
+                mod section_tests {
+                    use super::*;
+
+                    #[cfg(feature = "db")]
+                    mod given_a_database {
+                        use super::*;
+
+                        #[test]
+                        #[allow(unused_variables)]
+                        #[ignore]
+                        fn when_one() {
+                            if ::catchr::__private::skip(&["section tests", "given a database", "when one"], &[], false) {
+                                return;
+                            }
+
+                            {
+                                {
+                                    {
+                                        let x = 1;
+                                    }
+                                }
+                            }
+                        }
+
+                        #[test]
+                        #[should_panic(expected = "boom")]
+                        #[ignore]
+                        fn then_panics() {
+                            if ::catchr::__private::skip(&["section tests", "given a database", "then panics"], &[], false) {
+                                ::std::panic!("catchr: skipped (expected: boom)");
+                            }
+
+                            {
+                                {
+                                    {
+                                        panic!("boom");
+                                    }
+                                }
+                            }
+                        }
+
+                        #[test]
+                        #[ignore = "flaky"]
+                        fn then_flakes() {
+                            if ::catchr::__private::skip(&["section tests", "given a database", "then flakes"], &[], false) {
+                                return;
+                            }
+
+                            {
+                                {
+                                    {
+                                        assert!(true);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            )
+            ; "attributes"
+        )]
+        #[test_case(
+            r#"
+                section "tests" {
+                    let offset = 0;
+
+                    #[ignore]
+                    case "adds {a} and {b}" with [(1, 2, 3), (4, 5, 9)] as (a, b, expected) {
+                        assert_eq!(a + b + offset, expected);
+                    }
+                }
+            "#,
+            quote!(
+                mod section_tests {
+                    use super::*;
+
+                    mod case_adds_a_and_b {
+                        use super::*;
+
+                        #[test]
+                        #[ignore]
+                        fn case_0_adds_1_and_2() {
+                            if ::catchr::__private::skip(&["section tests", "case 0 adds 1 and 2"], &[], false) {
+                                return;
+                            }
+
+                            {
+                                let offset = 0;
+                                {
+                                    let (a, b, expected) = (1, 2, 3);
+                                    {
+                                        assert_eq!(a + b + offset, expected);
+                                    }
+                                }
+                            }
+                        }
+
+                        #[test]
+                        #[ignore]
+                        fn case_1_adds_4_and_5() {
+                            if ::catchr::__private::skip(&["section tests", "case 1 adds 4 and 5"], &[], false) {
+                                return;
+                            }
+
+                            {
+                                let offset = 0;
+                                {
+                                    let (a, b, expected) = (4, 5, 9);
+                                    {
+                                        assert_eq!(a + b + offset, expected);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            )
+            ; "examples"
+        )]
+        #[test_case(
+            r#"
+                section "tests" {
+                    given "a user" with [("bob", 3), ("alice", 5)] as (name, len) {
+                        let user = User::new(name);
+
+                        then "the name has the right length" {
+                            assert_eq!(user.name().len(), len);
+                        }
+                    }
+                }
+            "#,
+            quote!(
+                mod section_tests {
+                    use super::*;
+
+                    mod given_a_user {
+                        use super::*;
+
+                        mod given_0_a_user {
+                            use super::*;
+
+                            #[test]
+                            fn then_the_name_has_the_right_length() {
+                                if ::catchr::__private::skip(&["section tests", "given 0 a user", "then the name has the right length"], &[], false) {
+                                    return;
+                                }
+
+                                {
+                                    {
+                                        let (name, len) = ("bob", 3);
+                                        {
+                                            let user = User::new(name);
+                                            {
+                                                assert_eq!(user.name().len(), len);
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+
+                        mod given_1_a_user {
+                            use super::*;
+
+                            #[test]
+                            fn then_the_name_has_the_right_length() {
+                                if ::catchr::__private::skip(&["section tests", "given 1 a user", "then the name has the right length"], &[], false) {
+                                    return;
+                                }
+
+                                {
+                                    {
+                                        let (name, len) = ("alice", 5);
+                                        {
+                                            let user = User::new(name);
+                                            {
+                                                assert_eq!(user.name().len(), len);
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            )
+            ; "examples on a nested section"
+        )]
+        #[test_case(
+            r#"
+                section "tests" {
+                    case "at {x}, {y}" with [Point { x: 1, y: 2 }] as Point { x, y } {
+                        assert!(x < y);
+                    }
+                }
+            "#,
+            quote!(
+                mod section_tests {
+                    use super::*;
+
+                    mod case_at_x_y {
+                        use super::*;
+
+                        #[test]
+                        fn case_0_at_1_2() {
+                            if ::catchr::__private::skip(&["section tests", "case 0 at 1, 2"], &[], false) {
+                                return;
+                            }
+
+                            {
+                                {
+                                    let Point { x, y } = Point { x: 1, y: 2 };
+                                    {
+                                        assert!(x < y);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            )
+            ; "examples with a struct pattern"
+        )]
+        #[test_case(
+            r#"
+                section "tests" {
+                    let x = generate!(1, 2);
+
+                    case "one" {
+                        assert!(x > 0);
+                    }
+                }
+            "#,
+            quote!(
+                mod section_tests {
+                    use super::*;
+
+                    #[test]
+                    fn case_one() {
+                        if ::catchr::__private::skip(&["section tests", "case one"], &[], false) {
+                            return;
+                        }
+
+                        let mut __catchr_generators =
+                            ::catchr::__private::Generators::new();
+
+                        while __catchr_generators.next_run() {
+                            {
+                                let x = __catchr_generators.pick("x", [1, 2]);
+                                {
+                                    assert!(x > 0);
+                                }
+                            }
+                        }
+                    }
+                }
+            )
+            ; "generators"
+        )]
+        #[test_case(
+            r#"
+                section "tests" -> Result<(), Error> {
+                    let x = parse("1")?;
+
+                    case "one" {
+                        assert_eq!(x, parse("1")?);
+                    }
+                }
+            "#,
+            quote!(
+                mod section_tests {
+                    use super::*;
+
+                    #[test]
+                    fn case_one() -> Result<(), Error> {
+                        if ::catchr::__private::skip(&["section tests", "case one"], &[], false) {
+                            return ::std::result::Result::Ok(());
+                        }
+
+                        #[allow(clippy::redundant_closure_call)]
+                        let __catchr_result: Result<(), Error> =
+                            (|| -> Result<(), Error> {
+                                {
+                                    let x = parse("1")?;
+                                    {
+                                        assert_eq!(x, parse("1")?);
+                                    }
+                                }
+
+                                ::std::result::Result::Ok(())
+                            })();
+
+                        if let ::std::result::Result::Err(err) = &__catchr_result {
+                            eprintln!(
+                                "catchr: {} failed: {:?}",
+                                "section \"tests\" / case \"one\"",
+                                err
+                            );
+                            return __catchr_result;
+                        }
+
+                        ::std::result::Result::Ok(())
+                    }
+                }
+            )
+            ; "returns"
+        )]
+        #[test_case(
+            r#"
+                section "tests" -> Result<(), Error> {
+                    let x = parse("1")?;
+
+                    #[should_panic(expected = "boom")]
+                    when "it panics" {
+                        case "one" {
+                            boom(x)?;
+                        }
+                    }
+                }
+            "#,
+            quote!(
+                mod section_tests {
+                    use super::*;
+
+                    mod when_it_panics {
+                        use super::*;
+
+                        #[test]
+                        #[should_panic(expected = "boom")]
+                        fn case_one() {
+                            if ::catchr::__private::skip(&["section tests", "when it panics", "case one"], &[], false) {
+                                ::std::panic!("catchr: skipped (expected: boom)");
+                            }
+
+                            #[allow(clippy::redundant_closure_call)]
+                            let __catchr_result: Result<(), Error> =
+                                (|| -> Result<(), Error> {
+                                    {
+                                        let x = parse("1")?;
+                                        {
+                                            {
+                                                boom(x)?;
+                                            }
+                                        }
+                                    }
+
+                                    ::std::result::Result::Ok(())
+                                })();
+
+                            if let ::std::result::Result::Err(err) = &__catchr_result {
+                                panic!(
+                                    "catchr: {} failed: {:?}",
+                                    "section \"tests\" / when \"it panics\" / case \"one\"",
+                                    err
+                                );
+                            }
+                        }
+                    }
+                }
+            )
+            ; "returns with should panic"
+        )]
+        #[test_case(
+            r#"
+                section "tests" {
+                    let dir = create_dir();
+                    defer {
+                        remove_dir(&dir);
+                    }
+
+                    case "one" {
+                        assert!(dir.exists());
+                    }
+                }
+            "#,
+            quote!(
+                mod section_tests {
+                    use super::*;
+
+                    #[test]
+                    fn case_one() {
+                        if ::catchr::__private::skip(&["section tests", "case one"], &[], false) {
+                            return;
+                        }
+
+                        {
+                            let dir = create_dir();
+                            let __catchr_outcome =
+                                ::catchr::__private::catch_unwind(|| {
+                                    {
+                                        assert!(dir.exists());
+                                    }
+                                });
+                            let __catchr_cleanup =
+                                ::catchr::__private::catch_unwind(|| {
+                                    remove_dir(&dir);
+                                });
+                            ::catchr::__private::resume_deferred(
+                                __catchr_outcome,
+                                __catchr_cleanup,
+                            );
+                        }
+                    }
+                }
+            )
+            ; "defer"
+        )]
+        #[test_case(
+            r#"
+                section "tests" {
+                    case "sync" {
+                        assert!(true);
+                    }
+
+                    async case "async" {
+                        fetch().await;
+                    }
+                }
+            "#,
+            quote!(
+                mod section_tests {
+                    use super::*;
+
+                    #[test]
+                    fn case_sync() {
+                        if ::catchr::__private::skip(&["section tests", "case sync"], &[], false) {
+                            return;
+                        }
+
+                        {
+                            {
+                                assert!(true);
+                            }
+                        }
+                    }
+
+                    #[tokio::test]
+                    async fn case_async() {
+                        if ::catchr::__private::skip(&["section tests", "case async"], &[], false) {
+                            return;
+                        }
+
+                        {
+                            {
+                                fetch().await;
+                            }
+                        }
+                    }
+                }
+            )
+            ; "async sections"
+        )]
+        #[test_case(
+            r#"
+                #[timeout(2s)]
+                section "tests" {
+                    case "one" {
+                        assert!(true);
+                    }
+
+                    #[timeout(500ms)]
+                    case "two" {
+                        assert!(true);
+                    }
+                }
+            "#,
+            quote!(
+                mod section_tests {
+                    use super::*;
+
+                    #[test]
+                    fn case_one() {
+                        if ::catchr::__private::skip(&["section tests", "case one"], &[], false) {
+                            return;
+                        }
+
+                        ::catchr::__private::run_with_timeout(
+                            ::std::time::Duration::from_millis(2000u64),
+                            "section \"tests\" / case \"one\" timed out after 2s",
+                            move || {
+                                {
+                                    {
+                                        assert!(true);
+                                    }
+                                }
+                            }
+                        )
+                    }
+
+                    #[test]
+                    fn case_two() {
+                        if ::catchr::__private::skip(&["section tests", "case two"], &[], false) {
+                            return;
+                        }
+
+                        ::catchr::__private::run_with_timeout(
+                            ::std::time::Duration::from_millis(500u64),
+                            "section \"tests\" / case \"two\" timed out after 500ms",
+                            move || {
+                                {
+                                    {
+                                        assert!(true);
+                                    }
+                                }
+                            }
+                        )
+                    }
+                }
+            )
+            ; "timeouts"
+        )]
+        #[test_case(
+            r#"
+                #[runtime_sections]
+                section "tests" {
+                    let x = 1;
+
+                    when "one" {
+                        then "two" {
+                            assert_eq!(x, 1);
+                        }
+                    }
+
+                    case "adds {a}" with [1] as a {
+                        assert_eq!(a, x);
+                    }
+                }
+            "#,
+            quote!(
+                #[test]
+                fn section_tests() {
+                    if ::catchr::__private::skip(&["section tests"], &[], true) {
+                        return;
+                    }
+
+                    let __catchr_tracker =
+                        ::catchr::__private::Tracker::new("section \"tests\"");
+
+                    while __catchr_tracker.start_pass() {
+                        #[allow(unreachable_code)]
+                        let __catchr_outcome = ::catchr::__private::catch_unwind(
+                            || -> ::std::result::Result<(), ::std::convert::Infallible> {
+                                {
+                                    let x = 1;
+
+                                    if __catchr_tracker.enter("when \"one\"") {
+                                        {
+                                            if __catchr_tracker.enter("then \"two\"") {
+                                                {
+                                                    assert_eq!(x, 1);
+                                                }
+                                                #[allow(unreachable_code)]
+                                                __catchr_tracker.leave();
+                                            }
+                                        }
+                                        #[allow(unreachable_code)]
+                                        __catchr_tracker.leave();
+                                    }
+
+                                    if __catchr_tracker.enter("case \"0 adds 1\"") {
+                                        {
+                                            let a = 1;
+                                            assert_eq!(a, x);
+                                        }
+                                        #[allow(unreachable_code)]
+                                        __catchr_tracker.leave();
+                                    }
+                                }
+
+                                ::std::result::Result::Ok(())
+                            }
+                        );
+                        __catchr_tracker.end_pass(__catchr_outcome);
+                    }
+
+                    __catchr_tracker.finish();
+                }
+            )
+            ; "runtime sections"
+        )]
+        #[test_case(
+            r#"
+                #[shared_setup]
+                section "tests" {
+                    let x = 1;
+
+                    when "one" {
+                        then "two" {
+                            assert_eq!(x, 1);
+                        }
+                    }
+
+                    case "adds {a}" with [1] as a {
+                        assert_eq!(a, x);
+                    }
+                }
+            "#,
+            quote!(
+                mod section_tests {
+                    use super::*;
+
+                    #[allow(dead_code)]
+                    fn __catchr_run(__catchr_path: &[usize]) {
+                        {
+                            let x = 1;
+
+                            if __catchr_path[0] == 0 {
+                                {
+                                    if __catchr_path[1] == 0 {
+                                        {
+                                            assert_eq!(x, 1);
+                                        }
+                                    }
+                                }
+                            }
+
+                            if __catchr_path[0] == 1 {
+                                if __catchr_path[1] == 0 {
+                                    {
+                                        let a = 1;
+                                        assert_eq!(a, x);
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    mod when_one {
+                        use super::*;
+
+                        #[test]
+                        fn then_two() {
+                            if ::catchr::__private::skip(&["section tests", "when one", "then two"], &[], false) {
+                                return;
+                            }
+
+                            __catchr_run(&[0, 0])
+                        }
+                    }
+
+                    mod case_adds_a {
+                        use super::*;
+
+                        #[test]
+                        fn case_0_adds_1() {
+                            if ::catchr::__private::skip(&["section tests", "case 0 adds 1"], &[], false) {
+                                return;
+                            }
+
+                            __catchr_run(&[1, 0])
+                        }
+                    }
+                }
+            )
+            ; "shared setup"
+        )]
+        #[test_case(
+            r#"
+                section "tests" {
+                    let x = 1;
+
+                    xwhen "one" {
+                        then "two" {
+                            assert_eq!(x, 1);
+                        }
+                    }
+
+                    pending "not written yet" {}
+
+                    #[ignore = "flaky"]
+                    xthen "three" {
+                        assert!(true);
+                    }
+                }
+            "#,
+            quote!(
+                mod section_tests {
+                    use super::*;
+
+                    mod when_one {
+                        use super::*;
+
+                        #[test]
+                        #[ignore = "pending: one"]
+                        fn then_two() {}
+                    }
+
+                    #[test]
+                    #[ignore = "pending: not written yet"]
+                    fn pending_not_written_yet() {}
+
+                    #[test]
+                    #[ignore = "flaky"]
+                    fn then_three() {}
+                }
+            )
+            ; "pending sections"
+        )]
+        #[test_case(
+            r#"
+                #[shared_setup]
+                section "tests" {
+                    let x = 1;
+
+                    xthen "one" {
+                        assert_eq!(x, 1);
+                    }
+
+                    then "two" {
+                        assert_eq!(x, 2);
+                    }
+                }
+            "#,
+            quote!(
+                mod section_tests {
+                    use super::*;
+
+                    #[allow(dead_code)]
+                    fn __catchr_run(__catchr_path: &[usize]) {
+                        {
+                            let x = 1;
+
+                            if __catchr_path[0] == 1 {
+                                {
+                                    assert_eq!(x, 2);
+                                }
+                            }
+                        }
+                    }
+
+                    #[test]
+                    #[ignore = "pending: one"]
+                    fn then_one() {}
+
+                    #[test]
+                    fn then_two() {
+                        if ::catchr::__private::skip(&["section tests", "then two"], &[], false) {
+                            return;
+                        }
+
+                        __catchr_run(&[1])
+                    }
+                }
+            )
+            ; "pending shared setup"
+        )]
+        fn parse_and_quote(s: &str, exp: TokenStream) {
+            let section = syn::parse_str::<Section>(s).unwrap();
+            let section = section.to_token_stream();
+
+            assert_eq!(exp.to_string(), section.to_string());
+        }
+    }
+
+    #[test]
+    fn focused_sections() {
+        let section = syn::parse_str::<Section>(
+            r#"
+                section "tests" {
+                    fwhen "one" {
+                        then "two" {}
+                    }
+
+                    #[ignore = "flaky"]
+                    then "three" {}
+
+                    #[runtime_sections]
+                    when "four" {
+                        fthen "five" {}
+                    }
+
+                    then "six" {}
+                }
+            "#,
+        )
+        .unwrap();
+
+        let mut scope = Scope::empty();
+        scope.push_focus_mode();
+
+        let tokens = section.quote_inner(scope).to_string();
+        let ignored = |name: &str| {
+            tokens.contains(&format!(
+                "# [ignore = \"not focused\"] fn {} ()",
+                name
+            ))
+        };
+
+        assert!(!ignored("then_two"));
+        assert!(!ignored("then_three"));
+        assert!(tokens.contains("# [ignore = \"flaky\"] fn then_three ()"));
+        assert!(!ignored("when_four"));
+        assert!(ignored("then_six"));
+        assert_eq!(tokens.matches("not focused").count(), 1);
+    }
+
+    #[test]
+    fn await_in_sync_test() {
+        let section = syn::parse_str::<Section>(
+            r#"
+                when "fetching" {
+                    let x = fetch().await;
+
+                    then "x is fetched" {
+                        assert_eq!(x, 1);
+                    }
+                }
+            "#,
+        )
+        .unwrap();
+
+        assert!(section.to_token_stream().to_string().contains(
+            "`then \\\"x is fetched\\\"` uses `.await`, but it isn't async"
+        ));
+    }
+
+    #[test_case(
+        "#[ignore] then \"x\" {}", "attributes" ; "attributes"
+    )]
+    #[test_case("async then \"x\" {}", "`async`" ; "async sections")]
+    #[test_case(
+        "then \"x\" -> Result<(), Error> {}", "return types" ; "return types"
+    )]
+    fn runtime_sections_unsupported(nested: &str, unsupported: &str) {
+        let section = syn::parse_str::<Section>(&format!(
+            "#[runtime_sections] section \"tests\" {{ {} }}",
+            nested
+        ))
+        .unwrap();
+
+        assert!(section.to_token_stream().to_string().contains(&format!(
+            "{} can't be used on sections nested in runtime sections",
+            unsupported
+        )));
+    }
+
+    #[test]
+    fn runtime_sections_reject_duplicate_names() {
+        let section = syn::parse_str::<Section>(
+            r#"
+                #[runtime_sections]
+                section "tests" {
+                    when "a" {
+                        then "b" {}
+                        then "b" {
+                            assert!(false);
+                        }
+                    }
+                }
+            "#,
+        )
+        .unwrap();
+
+        assert!(section.to_token_stream().to_string().contains(
+            "`then \\\"b\\\"` generates the same test name (`then_b`) as \
+             `then \\\"b\\\"`"
+        ));
+    }
+
+    #[test_case(
+        "#[shared_setup] then \"x\" {}",
+        "`#[runtime_sections]` and `#[shared_setup]`"
+        ; "nested shared setup"
+    )]
+    #[test_case("async then \"x\" {}", "`async`" ; "async sections")]
+    #[test_case(
+        "then \"x\" -> Result<(), Error> {}", "return types" ; "return types"
+    )]
+    fn shared_setup_unsupported(nested: &str, unsupported: &str) {
+        let section = syn::parse_str::<Section>(&format!(
+            "#[shared_setup] section \"tests\" {{ {} }}",
+            nested
+        ))
+        .unwrap();
+
+        assert!(section.to_token_stream().to_string().contains(&format!(
+            "{} can't be used on sections nested in `#[shared_setup]` \
+             sections",
+            unsupported
+        )));
+    }
+
+    /// A spec with 100 tests, and a lot of setup shared between them.
+    fn large_spec(attr: &str) -> String {
+        let whens: String = (0..10)
+            .map(|when| {
+                let thens: String = (0..10)
+                    .map(|then| {
+                        format!(
+                            "then \"{}\" {{ assert_eq!(v.len(), w.len()); }}",
+                            then
+                        )
+                    })
+                    .collect();
+
+                format!(
+                    "when \"{}\" {{ let w = v.clone(); w.iter().count(); {} }}",
+                    when, thens
+                )
+            })
+            .collect();
+
+        format!(
+            "{} section \"large\" {{ let v = vec![1, 2, 3]; {} {} }}",
+            attr,
+            "let mut x = 0; x += v.len(); assert_eq!(x, 3);".repeat(10),
+            whens
+        )
+    }
+
+    #[test]
+    fn shared_setup_on_a_leaf() {
+        let section = syn::parse_str::<Section>(
+            r#"
+                #[shared_setup]
+                then "x" {
+                    assert!(true);
+                }
+            "#,
+        )
+        .unwrap();
+
+        assert!(section.to_token_stream().to_string().contains(
+            "`#[shared_setup]` can only be used on sections with nested \
+             sections"
+        ));
+    }
+
+    /// Only the size of the generated code is checked here - the compile
+    /// times were measured by hand, see the README.
+    #[test]
+    fn shared_setup_reduces_generated_code() {
+        // The length of the whole generated code, and of the code without the
+        // filter check every test starts with either way
+        let quote_len = |attr| {
+            let section = syn::parse_str::<Section>(&large_spec(attr)).unwrap();
+
+            let mut tokens = section.to_token_stream().to_string();
+            let len = tokens.len();
+
+            let prologue = "if :: catchr :: __private :: skip";
+
+            while let Some(start) = tokens.find(prologue) {
+                let end = "{ return ; } ";
+                let len = tokens[start..].find(end).unwrap() + end.len();
+
+                tokens.replace_range(start..start + len, "");
+            }
+
+            (len, tokens.len())
+        };
+
+        let (copied, copied_without_filter) = quote_len("");
+        let (shared, shared_without_filter) = quote_len("#[shared_setup]");
+
+        assert!(
+            shared * 3 < copied,
+            "{} bytes with a shared setup, {} without",
+            shared,
+            copied
+        );
+        assert!(
+            shared_without_filter * 5 < copied_without_filter,
+            "{} bytes with a shared setup, {} without, leaving out the filter",
+            shared_without_filter,
+            copied_without_filter
+        );
+    }
+
+    #[test]
+    fn dynamic_section_requires_runtime_sections() {
+        let section = syn::parse_str::<Section>(
+            r#"
+                section "users" {
+                    for id in ids {
+                        dynamic_section!(id, {});
+                    }
+                }
+            "#,
+        )
+        .unwrap();
+
+        assert!(section.to_token_stream().to_string().contains(
+            "`dynamic_section!` can only be used within sections marked with \
+             `#[runtime_sections]`"
+        ));
+    }
+
+    mod tokio {
+        use test_case::test_case;
+
+        use super::*;
+
+        #[test_case(
+            r#"
+                section "tests" {
+                    let x = 1;
+                    when "hello" {
+                        assert!(true);
+                        then "whatever" {
+                            assert!(true);
+                        }
+                    }
+
+                    assert_eq!(x, 1);
+                }
+            "#,
+            quote!(
+                mod section_tests {
+                    use super::*;
+
+                    mod when_hello {
+                        use super::*;
+
+                        #[tokio::test]
+                        async fn then_whatever() {
+                            if ::catchr::__private::skip(&["section tests", "when hello", "then whatever"], &[], false) {
+                                return;
+                            }
+
+                            {
+                                let x = 1;
+                                {
+                                    assert!(true);
+                                    {
+                                        assert!(true);
+                                    }
+                                }
+                                assert_eq!(x, 1);
+                            }
+                        }
+                    }
+                }
+            )
+        )]
+        #[test_case(
+            r#"
+                section "tests" {
+                    assert!(1 == 1);
+
+                    case "one" {
+                        assert!(2 == 2);
+                    }
+
+                    assert!(3 == 3);
+
+                    case "two" {
+                        assert!(4 == 4);
+                    }
+
+                    assert!(5 == 5);
+                }
+            "#,
+            quote!(
+                mod section_tests {
+                    use super::*;
+
+                    #[tokio::test]
+                    async fn case_one() {
+                        if ::catchr::__private::skip(&["section tests", "case one"], &[], false) {
+                            return;
+                        }
+
+                        {
+                            assert!(1 == 1);
+                            {
+                                assert!(2 == 2);
+                            }
+                            assert!(3 == 3);
+                            assert!(5 == 5);
+                        }
+                    }
+
+                    #[tokio::test]
+                    async fn case_two() {
+                        if ::catchr::__private::skip(&["section tests", "case two"], &[], false) {
+                            return;
+                        }
+
+                        {
+                            assert!(1 == 1);
+                            assert!(3 == 3);
+                            {
+                                assert!(4 == 4);
+                            }
+                            assert!(5 == 5);
+                        }
+                    }
+                }
+            )
+        )]
+        #[test_case(
+            r#"
+                section "tests" {
+                    when "reading" -> io::Result<()> {
+                        let s = read().await?;
+                    }
+                }
+            "#,
+            quote!(
+                mod section_tests {
+                    use super::*;
+
+                    #[tokio::test]
+                    async fn when_reading() -> io::Result<()> {
+                        if ::catchr::__private::skip(&["section tests", "when reading"], &[], false) {
+                            return ::std::result::Result::Ok(());
+                        }
+
+                        let __catchr_result: io::Result<()> = async {
+                            {
+                                {
+                                    let s = read().await?;
+                                }
+                            }
+
+                            ::std::convert::identity::<io::Result<()> >(
+                                ::std::result::Result::Ok(())
+                            )
+                        }
+                        .await;
+
+                        if let ::std::result::Result::Err(err) = &__catchr_result {
+                            eprintln!(
+                                "catchr: {} failed: {:?}",
+                                "section \"tests\" / when \"reading\"",
+                                err
+                            );
+                            return __catchr_result;
+                        }
+
+                        ::std::result::Result::Ok(())
+                    }
+                }
+            )
+        )]
+        fn parse_and_quote(s: &str, exp: TokenStream) {
+            let section = syn::parse_str::<Section>(s).unwrap();
+            let section = section
+                .with_mode(CatchrMode::Tokio(TokioArgs::default()))
+                .to_token_stream();
+
+            assert_eq!(exp.to_string(), section.to_string());
+        }
+
+        #[test]
+        fn timeout() {
+            let section = syn::parse_str::<Section>(
+                r#"
+                    #[timeout(1m)]
+                    then "one" {
+                        assert!(true);
+                    }
+                "#,
+            )
+            .unwrap();
+
+            let section = section
+                .with_mode(CatchrMode::Tokio(TokioArgs::default()))
+                .to_token_stream();
+
+            #[rustfmt::skip]
+            let exp = quote!(
+                #[tokio::test]
+                async fn then_one() {
+                    if ::catchr::__private::skip(&["then one"], &[], false) {
+                        return;
+                    }
+
+                    match ::tokio::time::timeout(
+                        ::std::time::Duration::from_millis(60000u64),
+                        async {
+                            {
+                                assert!(true);
+                            }
+                        }
+                    )
+                    .await
+                    {
+                        ::std::result::Result::Ok(output) => output,
+                        ::std::result::Result::Err(_) => panic!(
+                            "{}",
+                            "then \"one\" timed out after 1m"
+                        ),
+                    }
+                }
+            );
+
+            assert_eq!(exp.to_string(), section.to_string());
+        }
 
-        for (idx, item) in self.body.items().iter().enumerate() {
-            if let SectionItem::Sep(section) = item {
-                let sb = self.body.get_stmts_before(idx);
-                let sa = self.body.get_stmts_after(idx);
+        #[test]
+        fn runtime_config() {
+            let section = syn::parse_str::<Section>(
+                r#"
+                    section "tests" {
+                        then "one" {
+                            assert!(true);
+                        }
 
-                let mut scope = scope.clone();
-                scope.push_mut(&sb, &sa);
+                        #[tokio(worker_threads = 1, start_paused = true)]
+                        then "two" {
+                            assert!(true);
+                        }
+                    }
+                "#,
+            )
+            .unwrap();
 
-                let inner = section.quote_inner(scope);
+            let args = syn::parse_str(
+                r#"flavor = "multi_thread", worker_threads = 4"#,
+            )
+            .unwrap();
 
-                stream.push(inner);
-            }
-        }
+            let section =
+                section.with_mode(CatchrMode::Tokio(args)).to_token_stream();
 
-        let name = self.quote_name();
+            let exp = quote! {
+                mod section_tests {
+                    use super::*;
 
-        tokens.append_all(quote! {
-            mod #name {
-                use super::*;
+                    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+                    async fn then_one() {
+                        if ::catchr::__private::skip(&["section tests", "then one"], &[], false) {
+                            return;
+                        }
 
-                #(#stream)*
-            }
-        });
-    }
-}
+                        {
+                            {
+                                assert!(true);
+                            }
+                        }
+                    }
 
-impl ToTokens for Section {
-    fn to_tokens(&self, tokens: &mut TokenStream) {
-        let scope = Scope::empty();
+                    #[tokio::test(
+                        flavor = "multi_thread",
+                        worker_threads = 1,
+                        start_paused = true
+                    )]
+                    async fn then_two() {
+                        if ::catchr::__private::skip(&["section tests", "then two"], &[], false) {
+                            return;
+                        }
 
-        self.to_tokens_inner(scope, tokens);
-    }
-}
+                        {
+                            {
+                                assert!(true);
+                            }
+                        }
+                    }
+                }
+            };
 
-impl Parse for Section {
-    fn parse(input: ParseStream) -> parse::Result<Self> {
-        let section_keyword: SectionKeyword = input.parse()?;
-        let name: syn::Lit = input.parse()?;
-        let name = extract_literal_string(name).ok_or_else(|| {
-            parse::Error::new(Span::call_site(), "Invalid section literal")
-        })?;
+            assert_eq!(exp.to_string(), section.to_string());
+        }
 
-        let content;
-        syn::braced!(content in input);
-        let inner_body = content.parse::<SectionBody>()?;
+        #[test]
+        fn runtime_config_requires_tokio() {
+            let section = syn::parse_str::<Section>(
+                r#"
+                    #[tokio(flavor = "multi_thread")]
+                    then "one" {
+                        assert!(true);
+                    }
+                "#,
+            )
+            .unwrap();
 
-        Ok(Section::new(section_keyword, name, inner_body))
+            assert!(section.to_token_stream().to_string().contains(
+                "`#[tokio(...)]` can only be used on tests run by tokio"
+            ));
+        }
     }
-}
-
-#[cfg(test)]
-mod tests {
-
-    use super::*;
 
-    mod regular {
+    mod async_std {
         use test_case::test_case;
 
         use super::*;
@@ -156,34 +2645,26 @@ mod tests {
         #[test_case(
             r#"
                 section "tests" {
-                    let x = 1;
-                    when "hello" {
-                        assert!(true);
-                        then "whatever" {
-                            assert!(true);
-                        }
-                    }
+                    let x = setup().await;
 
-                    assert_eq!(x, 1);
+                    case "one" {
+                        assert_eq!(x, 1);
+                    }
                 }
             "#,
             quote!(
                 mod section_tests {
                     use super::*;
 
-                    mod when_hello {
-                        use super::*;
+                    #[async_std::test]
+                    async fn case_one() {
+                        if ::catchr::__private::skip(&["section tests", "case one"], &[], false) {
+                            return;
+                        }
 
-                        #[test]
-                        fn then_whatever() {
+                        {
+                            let x = setup().await;
                             {
-                                let x = 1;
-                                {
-                                    assert!(true);
-                                    {
-                                        assert!(true);
-                                    }
-                                }
                                 assert_eq!(x, 1);
                             }
                         }
@@ -191,22 +2672,28 @@ mod tests {
                 }
             )
         )]
+        fn parse_and_quote(s: &str, exp: TokenStream) {
+            let section = syn::parse_str::<Section>(s).unwrap();
+            let section =
+                section.with_mode(CatchrMode::AsyncStd).to_token_stream();
+
+            assert_eq!(exp.to_string(), section.to_string());
+        }
+    }
+
+    mod smol {
+        use test_case::test_case;
+
+        use super::*;
+
         #[test_case(
             r#"
                 section "tests" {
-                    assert!(1 == 1);
+                    let x = setup().await;
 
                     case "one" {
-                        assert!(2 == 2);
+                        assert_eq!(x, 1);
                     }
-
-                    assert!(3 == 3);
-
-                    case "two" {
-                        assert!(4 == 4);
-                    }
-
-                    assert!(5 == 5);
                 }
             "#,
             quote!(
@@ -215,39 +2702,31 @@ mod tests {
 
                     #[test]
                     fn case_one() {
-                        {
-                            assert!(1 == 1);
-                            {
-                                assert!(2 == 2);
-                            }
-                            assert!(3 == 3);
-                            assert!(5 == 5);
+                        if ::catchr::__private::skip(&["section tests", "case one"], &[], false) {
+                            return;
                         }
-                    }
 
-                    #[test]
-                    fn case_two() {
-                        {
-                            assert!(1 == 1);
-                            assert!(3 == 3);
+                        ::smol::block_on(async {
                             {
-                                assert!(4 == 4);
+                                let x = setup().await;
+                                {
+                                    assert_eq!(x, 1);
+                                }
                             }
-                            assert!(5 == 5);
-                        }
+                        })
                     }
                 }
             )
         )]
         fn parse_and_quote(s: &str, exp: TokenStream) {
             let section = syn::parse_str::<Section>(s).unwrap();
-            let section = section.to_token_stream();
+            let section = section.with_mode(CatchrMode::Smol).to_token_stream();
 
             assert_eq!(exp.to_string(), section.to_string());
         }
     }
 
-    mod tokio {
+    mod executor {
         use test_case::test_case;
 
         use super::*;
@@ -255,95 +2734,266 @@ mod tests {
         #[test_case(
             r#"
                 section "tests" {
-                    let x = 1;
-                    when "hello" {
-                        assert!(true);
-                        then "whatever" {
-                            assert!(true);
-                        }
-                    }
+                    let x = setup().await;
 
-                    assert_eq!(x, 1);
+                    case "one" {
+                        assert_eq!(x, 1);
+                    }
                 }
             "#,
             quote!(
                 mod section_tests {
                     use super::*;
 
-                    mod when_hello {
-                        use super::*;
+                    #[test]
+                    fn case_one() {
+                        if ::catchr::__private::skip(&["section tests", "case one"], &[], false) {
+                            return;
+                        }
 
-                        #[tokio::test]
-                        async fn then_whatever() {
+                        futures::executor::block_on(async {
                             {
-                                let x = 1;
+                                let x = setup().await;
                                 {
-                                    assert!(true);
-                                    {
-                                        assert!(true);
-                                    }
+                                    assert_eq!(x, 1);
                                 }
-                                assert_eq!(x, 1);
                             }
-                        }
+                        })
                     }
                 }
             )
         )]
-        #[test_case(
-            r#"
-                section "tests" {
-                    assert!(1 == 1);
+        fn parse_and_quote(s: &str, exp: TokenStream) {
+            let section = syn::parse_str::<Section>(s).unwrap();
+            let section = section
+                .with_mode(CatchrMode::Async(syn::parse_quote!(
+                    futures::executor::block_on
+                )))
+                .to_token_stream();
 
-                    case "one" {
-                        assert!(2 == 2);
-                    }
+            assert_eq!(exp.to_string(), section.to_string());
+        }
+    }
 
-                    assert!(3 == 3);
+    mod custom {
+        use test_case::test_case;
 
-                    case "two" {
-                        assert!(4 == 4);
-                    }
+        use super::*;
 
-                    assert!(5 == 5);
-                }
-            "#,
+        #[test_case(
+            false,
             quote!(
                 mod section_tests {
                     use super::*;
 
-                    #[tokio::test]
-                    async fn case_one() {
+                    #[test_log::test]
+                    #[serial]
+                    fn case_one() {
+                        if ::catchr::__private::skip(&["section tests", "case one"], &[], false) {
+                            return;
+                        }
+
                         {
-                            assert!(1 == 1);
+                            let x = setup();
                             {
-                                assert!(2 == 2);
+                                assert_eq!(x, 1);
                             }
-                            assert!(3 == 3);
-                            assert!(5 == 5);
                         }
                     }
+                }
+            )
+            ; "sync fn"
+        )]
+        #[test_case(
+            true,
+            quote!(
+                mod section_tests {
+                    use super::*;
+
+                    #[test_log::test]
+                    #[serial]
+                    async fn case_one() {
+                        if ::catchr::__private::skip(&["section tests", "case one"], &[], false) {
+                            return;
+                        }
 
-                    #[tokio::test]
-                    async fn case_two() {
                         {
-                            assert!(1 == 1);
-                            assert!(3 == 3);
+                            let x = setup();
                             {
-                                assert!(4 == 4);
+                                assert_eq!(x, 1);
                             }
-                            assert!(5 == 5);
                         }
                     }
                 }
             )
+            ; "async fn"
         )]
-        fn parse_and_quote(s: &str, exp: TokenStream) {
-            let section = syn::parse_str::<Section>(s).unwrap();
-            let section =
-                section.with_mode(CatchrMode::Tokio).to_token_stream();
+        fn parse_and_quote(is_async: bool, exp: TokenStream) {
+            let section = syn::parse_str::<Section>(
+                r#"
+                    section "tests" {
+                        let x = setup();
+
+                        case "one" {
+                            assert_eq!(x, 1);
+                        }
+                    }
+                "#,
+            )
+            .unwrap();
+
+            let section = section
+                .with_mode(CatchrMode::Custom {
+                    attrs: vec![
+                        syn::parse_quote!(#[test_log::test]),
+                        syn::parse_quote!(#[serial]),
+                    ],
+                    is_async,
+                })
+                .to_token_stream();
 
             assert_eq!(exp.to_string(), section.to_string());
         }
     }
+
+    #[cfg(feature = "proptest")]
+    mod forall {
+        use super::*;
+
+        #[test]
+        fn parse_and_quote() {
+            let section = syn::parse_str::<Section>(
+                r#"
+                    section "tests" {
+                        let offset = 1;
+
+                        forall "addition commutes" (a in 0..100u32, b in 0..100u32) {
+                            assert_eq!(a + b + offset, b + a + offset);
+                        }
+                    }
+                "#,
+            )
+            .unwrap();
+
+            #[rustfmt::skip]
+            let exp = quote!(
+                mod section_tests {
+                    use super::*;
+
+                    #[test]
+                    fn forall_addition_commutes() {
+                        if ::catchr::__private::skip(&["section tests", "forall addition commutes"], &[], false) {
+                            return;
+                        }
+
+                        let mut __catchr_runner =
+                            ::catchr::__private::proptest::test_runner::TestRunner::new(
+                                ::catchr::__private::proptest::test_runner::Config {
+                                    source_file: ::std::option::Option::Some(file!()),
+                                    ..::std::default::Default::default()
+                                },
+                            );
+
+                        let __catchr_result = __catchr_runner.run(
+                            &(0..100u32, 0..100u32,),
+                            |__catchr_input| {
+                                {
+                                    let offset = 1;
+                                    {
+                                        let (a, b,) = __catchr_input;
+                                        {
+                                            assert_eq!(
+                                                a + b + offset,
+                                                b + a + offset
+                                            );
+                                        }
+                                    }
+                                }
+
+                                ::std::result::Result::Ok(())
+                            }
+                        );
+
+                        if let ::std::result::Result::Err(err) = __catchr_result
+                        {
+                            panic!(
+                                "{}: {}",
+                                "section \"tests\" / forall \"addition commutes\"",
+                                err
+                            );
+                        }
+                    }
+                }
+            );
+
+            assert_eq!(exp.to_string(), section.to_token_stream().to_string());
+        }
+
+        #[test]
+        fn rejects_nested_forall() {
+            let section = syn::parse_str::<Section>(
+                r#"
+                    forall "outer" (a in 0..10) {
+                        forall "inner" (b in 0..10) {
+                            assert!(a + b < 20);
+                        }
+                    }
+                "#,
+            )
+            .unwrap();
+
+            assert!(section
+                .to_token_stream()
+                .to_string()
+                .contains("forall sections can't be nested"));
+        }
+    }
+
+    #[test_case(r#"then "a" {}"# => 1 ; "leaf")]
+    #[test_case(
+        r#"
+            when "a" {
+                then "b" {}
+                then "c" {}
+            }
+        "#
+        => 2
+        ; "nested"
+    )]
+    #[test_case(
+        r#"
+            when "a" with [1, 2, 3] as x {
+                then "b" {}
+                then "c" with [4, 5] as y {}
+            }
+        "#
+        => 9
+        ; "examples"
+    )]
+    #[test_case(
+        r#"
+            #[runtime_sections]
+            when "a" with [1, 2] as x {
+                then "b" {}
+                then "c" {}
+            }
+        "#
+        => 2
+        ; "runtime"
+    )]
+    fn count_tests(s: &str) -> usize {
+        syn::parse_str::<Section>(s).unwrap().count_tests()
+    }
+
+    #[cfg(not(feature = "proptest"))]
+    #[test]
+    fn forall_requires_feature() {
+        let err = syn::parse_str::<Section>(r#"forall "x" (a in 0..10) {}"#)
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "forall sections require the `proptest` feature of catchr"
+        );
+    }
 }
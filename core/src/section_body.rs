@@ -3,10 +3,11 @@ use quote::{quote, TokenStreamExt};
 use syn::parse::{self, Parse, ParseStream};
 
 use crate::catchr_mode::CatchrMode;
+use crate::defer::Defer;
+use crate::generate::Generate;
 use crate::scope::Scope;
 use crate::section::Section;
 use crate::section_item::SectionItem;
-use crate::section_keyword::SectionKeyword;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SectionBody {
@@ -18,7 +19,7 @@ impl SectionBody {
         self.items = self
             .items
             .into_iter()
-            .map(|item| item.with_mode(test_attribute))
+            .map(|item| item.with_mode(test_attribute.clone()))
             .collect();
 
         self
@@ -36,6 +37,14 @@ impl SectionBody {
         self.items.push(SectionItem::Stmt(stmt));
     }
 
+    fn push_generate(&mut self, generate: Generate) {
+        self.items.push(SectionItem::Generate(generate));
+    }
+
+    fn push_defer(&mut self, defer: Defer) {
+        self.items.push(SectionItem::Defer(defer));
+    }
+
     fn push_section(&mut self, item: Section) {
         self.items.push(SectionItem::Sep(item));
     }
@@ -44,22 +53,33 @@ impl SectionBody {
         self.items.iter().all(|item| item.is_stmt())
     }
 
-    pub fn get_stmts_before(&self, idx: usize) -> Vec<syn::Stmt> {
+    pub fn has_generators(&self) -> bool {
+        self.items.iter().any(|item| item.is_generate())
+    }
+
+    pub fn get_stmts_before(&self, idx: usize) -> Vec<SectionItem> {
         self.items
             .iter()
             .take(idx)
-            .filter_map(|i| i.stmt())
+            .filter(|i| i.is_stmt())
+            .cloned()
             .collect()
     }
 
-    pub fn get_stmts_after(&self, idx: usize) -> Vec<syn::Stmt> {
+    pub fn get_stmts_after(&self, idx: usize) -> Vec<SectionItem> {
         self.items
             .iter()
             .skip(idx + 1)
-            .filter_map(|i| i.stmt())
+            .filter(|i| i.is_stmt())
+            .cloned()
             .collect()
     }
 
+    /// The statements of a body without nested sections.
+    pub fn get_stmts(&self) -> Vec<SectionItem> {
+        self.items.iter().filter(|i| i.is_stmt()).cloned().collect()
+    }
+
     pub fn items(&self) -> &[SectionItem] {
         &self.items
     }
@@ -94,15 +114,21 @@ impl Parse for SectionBody {
         let mut body = SectionBody { items: vec![] };
 
         loop {
-            if SectionKeyword::peek(input) {
+            if Section::peek(input) {
                 let inner_section = input.parse()?;
 
                 body.push_section(inner_section);
+            } else if Defer::peek(input) {
+                body.push_defer(input.parse()?);
             } else if input.is_empty() {
                 break;
             } else {
                 let next = input.parse::<syn::Stmt>()?;
-                body.push_stmt(next);
+
+                match Generate::from_stmt(&next)? {
+                    Some(generate) => body.push_generate(generate),
+                    None => body.push_stmt(next),
+                }
             }
         }
 
@@ -1,4 +1,6 @@
 use crate::catchr_mode::CatchrMode;
+use crate::defer::Defer;
+use crate::generate::Generate;
 use crate::section::Section;
 
 #[allow(clippy::large_enum_variant)]
@@ -6,6 +8,8 @@ use crate::section::Section;
 pub enum SectionItem {
     Sep(Section),
     Stmt(syn::Stmt),
+    Generate(Generate),
+    Defer(Defer),
 }
 
 impl SectionItem {
@@ -19,12 +23,17 @@ impl SectionItem {
     }
 
     pub fn is_stmt(&self) -> bool {
-        matches!(self, Self::Stmt(_))
+        matches!(self, Self::Stmt(_) | Self::Generate(_) | Self::Defer(_))
+    }
+
+    pub fn is_generate(&self) -> bool {
+        matches!(self, Self::Generate(_))
     }
 
     pub fn stmt(&self) -> Option<syn::Stmt> {
         match self {
             Self::Stmt(inner) => Some(inner.clone()),
+            Self::Generate(generate) => Some(generate.to_stmt()),
             _ => None,
         }
     }
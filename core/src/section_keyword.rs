@@ -1,12 +1,15 @@
-use proc_macro2::Span;
 use syn::parse::{self, Parse, ParseStream};
 
+use crate::section_mark::SectionMark;
+
 mod kw {
     syn::custom_keyword!(when);
     syn::custom_keyword!(then);
     syn::custom_keyword!(given);
     syn::custom_keyword!(case);
     syn::custom_keyword!(section);
+    syn::custom_keyword!(forall);
+    syn::custom_keyword!(pending);
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -16,6 +19,8 @@ pub enum SectionKeyword {
     Given,
     Case,
     Section,
+    Forall,
+    Pending,
 }
 
 impl SectionKeyword {
@@ -26,6 +31,43 @@ impl SectionKeyword {
             Self::Given => "given".to_string(),
             Self::Case => "case".to_string(),
             Self::Section => "section".to_string(),
+            Self::Forall => "forall".to_string(),
+            Self::Pending => "pending".to_string(),
+        }
+    }
+
+    /// Parses a keyword, which can be prefixed to mark the section (like
+    /// `xthen` or `fthen`), returning the mark. `pending` sections are always
+    /// marked as pending.
+    pub fn parse_marked(
+        input: ParseStream,
+    ) -> parse::Result<(Self, Option<SectionMark>)> {
+        if let Some((keyword, mark)) = Self::peek_marked(input) {
+            input.parse::<syn::Ident>()?;
+
+            return Ok((keyword, Some(mark)));
+        }
+
+        let keyword: Self = input.parse()?;
+        let mark = match keyword {
+            Self::Pending => Some(SectionMark::Pending),
+            _ => None,
+        };
+
+        Ok((keyword, mark))
+    }
+
+    /// Checks for a prefixed keyword, returning the keyword and its mark.
+    fn peek_marked(input: ParseStream) -> Option<(Self, SectionMark)> {
+        let ident = input.fork().parse::<syn::Ident>().ok()?.to_string();
+        let mut chars = ident.chars();
+        let mark = SectionMark::from_prefix(chars.next()?)?;
+
+        // `pending` can't be marked - `xpending` would be the same, and
+        // `fpending` would focus on a section that doesn't run
+        match syn::parse_str(chars.as_str()).ok()? {
+            Self::Pending => None,
+            keyword => Some((keyword, mark)),
         }
     }
 }
@@ -41,8 +83,10 @@ impl SectionKeyword {
         test |= lk.peek(kw::section);
         test |= lk.peek(kw::then);
         test |= lk.peek(kw::case);
+        test |= lk.peek(kw::forall);
+        test |= lk.peek(kw::pending);
 
-        test
+        test || Self::peek_marked(i).is_some()
     }
 }
 
@@ -70,16 +114,20 @@ impl Parse for SectionKeyword {
             input.parse::<kw::section>()?;
 
             Ok(Self::Section)
+        } else if lk.peek(kw::forall) {
+            input.parse::<kw::forall>()?;
+
+            Ok(Self::Forall)
+        } else if lk.peek(kw::pending) {
+            input.parse::<kw::pending>()?;
+
+            Ok(Self::Pending)
         } else {
-            Err(parse::Error::new(
-                Span::call_site(),
-                "Invalid section keyword",
-            ))
+            Err(parse::Error::new(input.span(), "Invalid section keyword"))
         }
     }
 }
 
-#[cfg(test)]
 #[cfg(test)]
 mod tests {
     use test_case::test_case;
@@ -91,6 +139,8 @@ mod tests {
     #[test_case("section"   => SectionKeyword::Section  ; "section")]
     #[test_case("then"      => SectionKeyword::Then     ; "then")]
     #[test_case("case"      => SectionKeyword::Case     ; "case")]
+    #[test_case("forall"    => SectionKeyword::Forall   ; "forall")]
+    #[test_case("pending"   => SectionKeyword::Pending  ; "pending")]
     fn parse(s: &str) -> SectionKeyword {
         syn::parse_str(s).unwrap()
     }
@@ -100,7 +150,45 @@ mod tests {
     #[test_case(SectionKeyword::Section, "section"  ; "section")]
     #[test_case(SectionKeyword::Then,    "then"     ; "then")]
     #[test_case(SectionKeyword::Case,    "case"     ; "case")]
+    #[test_case(SectionKeyword::Forall,  "forall"   ; "forall")]
+    #[test_case(SectionKeyword::Pending, "pending"  ; "pending")]
     fn to_name(kw: SectionKeyword, exp: &str) {
         assert_eq!(&kw.to_name(), exp);
     }
+
+    #[test_case("then"      => (SectionKeyword::Then, None)     ; "then")]
+    #[test_case("forall"    => (SectionKeyword::Forall, None)   ; "forall")]
+    #[test_case(
+        "xthen" => (SectionKeyword::Then, Some(SectionMark::Pending))
+        ; "xthen"
+    )]
+    #[test_case(
+        "xcase" => (SectionKeyword::Case, Some(SectionMark::Pending))
+        ; "xcase"
+    )]
+    #[test_case(
+        "pending" => (SectionKeyword::Pending, Some(SectionMark::Pending))
+        ; "pending"
+    )]
+    #[test_case(
+        "fthen" => (SectionKeyword::Then, Some(SectionMark::Focused))
+        ; "fthen"
+    )]
+    #[test_case(
+        "fforall" => (SectionKeyword::Forall, Some(SectionMark::Focused))
+        ; "fforall"
+    )]
+    fn parse_marked(s: &str) -> (SectionKeyword, Option<SectionMark>) {
+        syn::parse::Parser::parse_str(SectionKeyword::parse_marked, s).unwrap()
+    }
+
+    #[test_case("xpending" ; "xpending")]
+    #[test_case("fpending" ; "fpending")]
+    #[test_case("xyz" ; "unknown")]
+    #[test_case("ffthen" ; "double prefix")]
+    #[test_case("x" ; "x")]
+    fn parse_marked_error(s: &str) {
+        syn::parse::Parser::parse_str(SectionKeyword::parse_marked, s)
+            .unwrap_err();
+    }
 }
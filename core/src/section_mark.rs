@@ -0,0 +1,46 @@
+/// A mark put on a section by prefixing its keyword with a letter, as in
+/// RSpec and Jasmine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionMark {
+    /// `xthen "..." { ... }` - see `Section::with_pending`.
+    Pending,
+    /// `fthen "..." { ... }` - see `Section::with_focused`.
+    Focused,
+}
+
+impl SectionMark {
+    pub fn from_prefix(prefix: char) -> Option<Self> {
+        match prefix {
+            'x' => Some(Self::Pending),
+            'f' => Some(Self::Focused),
+            _ => None,
+        }
+    }
+
+    pub fn prefix(&self) -> char {
+        match self {
+            Self::Pending => 'x',
+            Self::Focused => 'f',
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case('x' => Some(SectionMark::Pending) ; "pending")]
+    #[test_case('f' => Some(SectionMark::Focused) ; "focused")]
+    #[test_case('y' => None ; "unknown")]
+    fn from_prefix(prefix: char) -> Option<SectionMark> {
+        SectionMark::from_prefix(prefix)
+    }
+
+    #[test_case(SectionMark::Pending ; "pending")]
+    #[test_case(SectionMark::Focused ; "focused")]
+    fn prefix_roundtrip(mark: SectionMark) {
+        assert_eq!(SectionMark::from_prefix(mark.prefix()), Some(mark));
+    }
+}
@@ -0,0 +1,104 @@
+use proc_macro2::{Ident, Literal, Span, TokenStream};
+use quote::quote;
+
+use crate::catchr_mode::CatchrMode;
+use crate::returns::Returns;
+
+/// Checks for the `#[shared_setup]` attribute. The statements of a section
+/// with it (and of its nested sections) are generated only once, into a
+/// function running the path through them given by each of the tests,
+/// instead of being copied into every test.
+pub fn is_shared_attr(attr: &syn::Attribute) -> bool {
+    attr.path.is_ident("shared_setup")
+}
+
+fn run_fn() -> Ident {
+    Ident::new("__catchr_run", Span::call_site())
+}
+
+fn path() -> Ident {
+    Ident::new("__catchr_path", Span::call_site())
+}
+
+/// A nested section, entered by the tests whose path goes through it. Its
+/// `cfg` attributes are kept, so that the code of a section that's compiled
+/// out is too.
+pub fn quote_branch(
+    depth: usize,
+    idx: usize,
+    attrs: &[syn::Attribute],
+    inner: TokenStream,
+) -> syn::Stmt {
+    let path = path();
+    let depth = Literal::usize_unsuffixed(depth);
+    let idx = Literal::usize_unsuffixed(idx);
+    let cfgs = attrs.iter().filter(|attr| attr.path.is_ident("cfg"));
+
+    syn::parse_quote! {
+        #(#cfgs)*
+        if #path[#depth] == #idx {
+            #inner
+        }
+    }
+}
+
+/// The function running a path through the sections, with the statements of
+/// all of them.
+pub fn quote_run_fn(
+    inner: TokenStream,
+    returns: Option<&Returns>,
+    mode: &CatchrMode,
+) -> TokenStream {
+    let run_fn = run_fn();
+    let path = path();
+
+    let asyncness = if mode.is_async() {
+        quote!(async)
+    } else {
+        TokenStream::new()
+    };
+
+    let (output, inner) = match returns {
+        Some(returns) => {
+            let ty = returns.ty();
+
+            (
+                quote!(-> #ty),
+                quote! {
+                    #inner
+
+                    ::std::result::Result::Ok(())
+                },
+            )
+        }
+        None => (TokenStream::new(), inner),
+    };
+
+    quote! {
+        #[allow(dead_code)]
+        #asyncness fn #run_fn(#path: &[usize]) #output {
+            #inner
+        }
+    }
+}
+
+/// The body of a test, running its path through the sections.
+pub fn quote_call(
+    path: &[usize],
+    returns: Option<&Returns>,
+    mode: &CatchrMode,
+) -> TokenStream {
+    let run_fn = run_fn();
+    let path = path.iter().map(|idx| Literal::usize_unsuffixed(*idx));
+
+    let call = if mode.is_async() {
+        quote!(#run_fn(&[#(#path),*]).await)
+    } else {
+        quote!(#run_fn(&[#(#path),*]))
+    };
+
+    match returns {
+        Some(_) => quote!(#call?;),
+        None => call,
+    }
+}
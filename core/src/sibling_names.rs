@@ -0,0 +1,79 @@
+use syn::parse;
+
+use crate::section::Section;
+
+/// Keeps track of the names generated for sibling sections, so that two
+/// descriptions escaping to the same identifier (e.g. `then "x > 0"` and
+/// `then "x < 0"`) are reported at the offending description, instead of
+/// as a duplicate definition at the call site of the macro.
+#[derive(Default)]
+pub struct SiblingNames<'a> {
+    sections: Vec<(String, &'a Section)>,
+}
+
+impl<'a> SiblingNames<'a> {
+    pub fn insert(&mut self, section: &'a Section) -> parse::Result<()> {
+        let name = section.quote_name().to_string();
+
+        if let Some((_, previous)) =
+            self.sections.iter().find(|(other, _)| *other == name)
+        {
+            return Err(parse::Error::new(
+                section.name_span(),
+                format!(
+                    "`{}` generates the same test name (`{}`) as `{}` - \
+                     consider rewording one of the descriptions",
+                    section.title(),
+                    name,
+                    previous.title()
+                ),
+            ));
+        }
+
+        self.sections.push((name, section));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::section_body::SectionBody;
+    use crate::section_keyword::SectionKeyword;
+
+    fn section(kind: SectionKeyword, name: &str) -> Section {
+        Section::new(kind, name, SectionBody::empty())
+    }
+
+    #[test]
+    fn accepts_unique_names() {
+        let a = section(SectionKeyword::Then, "x is positive");
+        let b = section(SectionKeyword::Then, "x is negative");
+        let c = section(SectionKeyword::When, "x is positive");
+
+        let mut names = SiblingNames::default();
+
+        assert!(names.insert(&a).is_ok());
+        assert!(names.insert(&b).is_ok());
+        assert!(names.insert(&c).is_ok());
+    }
+
+    #[test]
+    fn rejects_colliding_names() {
+        let a = section(SectionKeyword::Then, "x > 0");
+        let b = section(SectionKeyword::Then, "x < 0");
+
+        let mut names = SiblingNames::default();
+
+        assert!(names.insert(&a).is_ok());
+
+        let err = names.insert(&b).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "`then \"x < 0\"` generates the same test name (`then_x_0`) as \
+             `then \"x > 0\"` - consider rewording one of the descriptions"
+        );
+    }
+}
@@ -0,0 +1,526 @@
+use proc_macro2::{Delimiter, Group, Ident, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::parse::{self, Parse, ParseStream, Parser};
+
+use crate::catchr_mode::CatchrMode;
+use crate::describe::Describe;
+use crate::section_keyword::SectionKeyword;
+use crate::tokio_args::TokioArgs;
+
+/// The `#[spec]` attribute, an alternative to `describe!` that keeps the
+/// tests valid Rust, so that rustfmt and rust-analyzer can work with them.
+///
+/// Sections are written as macro calls, e.g. `when!("x", { ... })`, and
+/// lowered into the syntax of `describe!` before parsing:
+///
+/// ```text
+/// #[spec]
+/// mod tests {
+///     given!("an empty vec", {
+///         let mut v = vec![];
+///
+///         then!("it's empty", {
+///             assert!(v.is_empty());
+///         });
+///     });
+/// }
+/// ```
+///
+/// Put on a function, its body becomes a root section named after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Spec {
+    mode: CatchrMode,
+}
+
+impl Spec {
+    pub fn new(mode: CatchrMode) -> Self {
+        Self { mode }
+    }
+
+    pub fn mode(&self) -> &CatchrMode {
+        &self.mode
+    }
+
+    pub fn expand(&self, item: syn::Item) -> parse::Result<TokenStream> {
+        match item {
+            syn::Item::Mod(module) => self.expand_mod(module),
+            syn::Item::Fn(func) => self.expand_fn(func),
+            item => Err(parse::Error::new_spanned(
+                item,
+                "`#[spec]` can only be put on a module or a function",
+            )),
+        }
+    }
+
+    fn expand_mod(&self, module: syn::ItemMod) -> parse::Result<TokenStream> {
+        let items = match &module.content {
+            Some((_, items)) => items,
+            None => {
+                return Err(parse::Error::new_spanned(
+                    module,
+                    "`#[spec]` modules must be declared inline",
+                ))
+            }
+        };
+
+        // The attributes configuring the describe block are written at the
+        // top of the module
+        let (config, attrs): (Vec<_>, Vec<_>) = module
+            .attrs
+            .iter()
+            .partition(|attr| Describe::is_config_attr(attr));
+        let (inner, outer): (Vec<_>, Vec<_>) = attrs
+            .into_iter()
+            .partition(|attr| matches!(attr.style, syn::AttrStyle::Inner(_)));
+
+        let describe: Describe =
+            syn::parse2(lower(quote!(#(#config)* #(#items)*)))?;
+        let describe = describe.with_mode(self.mode.clone());
+
+        let syn::ItemMod {
+            vis,
+            mod_token,
+            ident,
+            ..
+        } = &module;
+
+        Ok(quote! {
+            #(#outer)*
+            #vis #mod_token #ident {
+                #(#inner)*
+                #describe
+            }
+        })
+    }
+
+    fn expand_fn(&self, func: syn::ItemFn) -> parse::Result<TokenStream> {
+        let syn::ItemFn {
+            attrs, sig, block, ..
+        } = func;
+
+        if !sig.inputs.is_empty() {
+            return Err(parse::Error::new_spanned(
+                sig.inputs,
+                "`#[spec]` functions can't take arguments",
+            ));
+        }
+
+        if !sig.generics.params.is_empty() {
+            return Err(parse::Error::new_spanned(
+                sig.generics,
+                "`#[spec]` functions can't be generic",
+            ));
+        }
+
+        let (config, attrs): (Vec<_>, Vec<_>) =
+            attrs.into_iter().partition(Describe::is_config_attr);
+        let asyncness = sig.asyncness;
+        let name = syn::LitStr::new(&sig.ident.to_string(), sig.ident.span());
+        let output = sig.output;
+        let stmts =
+            lower(block.stmts.iter().map(ToTokens::to_token_stream).collect());
+
+        let describe: Describe = syn::parse2(quote! {
+            #(#config)*
+            #(#attrs)*
+            #asyncness section #name #output { #stmts }
+        })?;
+
+        Ok(describe.with_mode(self.mode.clone()).to_token_stream())
+    }
+}
+
+impl Parse for Spec {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        if input.is_empty() {
+            return Ok(Self::new(CatchrMode::Regular));
+        }
+
+        let runtime: Ident = input.parse()?;
+
+        let mode = match runtime.to_string().as_str() {
+            "tokio" if input.peek(syn::token::Paren) => {
+                let content;
+                syn::parenthesized!(content in input);
+
+                CatchrMode::Tokio(content.parse()?)
+            }
+            "tokio" => CatchrMode::Tokio(TokioArgs::default()),
+            "async_std" => CatchrMode::AsyncStd,
+            "smol" => CatchrMode::Smol,
+            _ => {
+                return Err(parse::Error::new(
+                    runtime.span(),
+                    "expected `tokio`, `async_std` or `smol`",
+                ))
+            }
+        };
+
+        Ok(Self::new(mode))
+    }
+}
+
+/// Rewrites section calls into the syntax of `describe!`, e.g.
+/// `then!("x", async, [slow], { ... });` into `async then "x" [slow] {..}`.
+/// Anything else between the description and the body (like an example table
+/// or a return type) is kept as it is.
+fn lower(tokens: TokenStream) -> TokenStream {
+    let tokens: Vec<_> = tokens.into_iter().collect();
+    let mut lowered = TokenStream::new();
+    let mut idx = 0;
+
+    while idx < tokens.len() {
+        if let Some(section) = lower_call(&tokens[idx..]) {
+            lowered.extend(section);
+            idx += 3;
+
+            if tokens.get(idx).is_some_and(|token| is_punct(token, ';')) {
+                idx += 1;
+            }
+
+            continue;
+        }
+
+        lowered.extend(Some(match &tokens[idx] {
+            TokenTree::Group(group) => lower_group(group),
+            token => token.clone(),
+        }));
+
+        idx += 1;
+    }
+
+    lowered
+}
+
+fn lower_group(group: &Group) -> TokenTree {
+    let mut lowered = Group::new(group.delimiter(), lower(group.stream()));
+    lowered.set_span(group.span());
+
+    TokenTree::Group(lowered)
+}
+
+/// Lowers a call like `when!("x", { ... })` (or `defer!({ ... })`) at the
+/// start of `tokens`.
+fn lower_call(tokens: &[TokenTree]) -> Option<TokenStream> {
+    let (keyword, args) = match tokens {
+        [TokenTree::Ident(keyword), bang, TokenTree::Group(args), ..]
+            if is_punct(bang, '!')
+                && args.delimiter() == Delimiter::Parenthesis =>
+        {
+            (keyword, args)
+        }
+        _ => return None,
+    };
+
+    let args: Vec<_> = args.stream().into_iter().collect();
+
+    let body = match args.last() {
+        Some(TokenTree::Group(body))
+            if body.delimiter() == Delimiter::Brace =>
+        {
+            lower_group(body)
+        }
+        _ => return None,
+    };
+
+    if keyword == "defer" {
+        return match args.len() {
+            1 => Some(quote!(#keyword #body)),
+            _ => None,
+        };
+    }
+
+    // Including the marked ones, like `xthen!` or `fthen!`
+    if SectionKeyword::parse_marked
+        .parse2(keyword.to_token_stream())
+        .is_err()
+    {
+        return None;
+    }
+
+    let (name, mut modifiers) = match &args[..args.len() - 1] {
+        [name @ TokenTree::Literal(_), rest @ ..] => (name, rest),
+        _ => return None,
+    };
+
+    modifiers = strip_comma(modifiers);
+
+    let asyncness = match modifiers {
+        [TokenTree::Ident(asyncness), rest @ ..] if asyncness == "async" => {
+            modifiers = strip_comma(rest);
+            Some(asyncness)
+        }
+        _ => None,
+    };
+
+    let tags = match modifiers {
+        [TokenTree::Group(tags), rest @ ..]
+            if tags.delimiter() == Delimiter::Bracket =>
+        {
+            modifiers = strip_comma(rest);
+            Some(tags)
+        }
+        _ => None,
+    };
+
+    let modifiers = match modifiers {
+        [rest @ .., comma] if is_punct(comma, ',') => rest,
+        rest => rest,
+    };
+
+    Some(quote!(#asyncness #keyword #name #tags #(#modifiers)* #body))
+}
+
+fn strip_comma(tokens: &[TokenTree]) -> &[TokenTree] {
+    match tokens {
+        [comma, rest @ ..] if is_punct(comma, ',') => rest,
+        rest => rest,
+    }
+}
+
+fn is_punct(token: &TokenTree, ch: char) -> bool {
+    matches!(token, TokenTree::Punct(punct) if punct.as_char() == ch)
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+    use crate::section::Section;
+
+    fn expand(args: &str, item: &str) -> parse::Result<TokenStream> {
+        let spec: Spec = syn::parse_str(args)?;
+
+        spec.expand(syn::parse_str(item)?)
+    }
+
+    #[test_case(
+        r#"
+            mod tests {
+                use super::*;
+
+                given!("a vec", {
+                    let mut v = vec![1];
+
+                    #[ignore]
+                    when!("it's cleared", {
+                        v.clear();
+
+                        then!("it's empty", {
+                            assert!(v.is_empty());
+                        });
+                    });
+
+                    then!("it isn't empty", {
+                        assert!(!v.is_empty());
+                    })
+                });
+            }
+        "#,
+        r#"
+            use super::*;
+
+            given "a vec" {
+                let mut v = vec![1];
+
+                #[ignore]
+                when "it's cleared" {
+                    v.clear();
+
+                    then "it's empty" {
+                        assert!(v.is_empty());
+                    }
+                }
+
+                then "it isn't empty" {
+                    assert!(!v.is_empty());
+                }
+            }
+        "#
+        ; "sections"
+    )]
+    #[test_case(
+        r#"
+            mod tests {
+                section!("modifiers", {
+                    case!("adds {a}", with [(1, 2)] as (a, b), {
+                        assert_eq!(a + 1, b);
+                    });
+
+                    then!("awaits", async, {
+                        defer!({
+                            cleanup();
+                        });
+
+                        run().await;
+                    });
+
+                    then!("fails", -> Result<(), Error>, {
+                        fallible()?;
+                    });
+                });
+            }
+        "#,
+        r#"
+            section "modifiers" {
+                case "adds {a}" with [(1, 2)] as (a, b) {
+                    assert_eq!(a + 1, b);
+                }
+
+                async then "awaits" {
+                    defer {
+                        cleanup();
+                    }
+
+                    run().await;
+                }
+
+                then "fails" -> Result<(), Error> {
+                    fallible()?;
+                }
+            }
+        "#
+        ; "modifiers"
+    )]
+    #[test_case(
+        r#"
+            mod tests {
+                #![tag(slow, ignore)]
+
+                section!("tagged", [slow], {
+                    then!("awaits", async, [db], {
+                        run().await;
+                    });
+                });
+            }
+        "#,
+        r#"
+            #![tag(slow, ignore)]
+
+            section "tagged" [slow] {
+                async then "awaits" [db] {
+                    run().await;
+                }
+            }
+        "#
+        ; "tags"
+    )]
+    #[test_case(
+        r#"
+            mod tests {
+                #![pending_if_empty]
+
+                section!("later", {
+                    xthen!("disabled", {
+                        unimplemented!();
+                    });
+
+                    pending!("not written yet", {});
+
+                    fthen!("focused", {});
+                });
+            }
+        "#,
+        r#"
+            #![pending_if_empty]
+
+            section "later" {
+                xthen "disabled" {
+                    unimplemented!();
+                }
+
+                pending "not written yet" {}
+
+                fthen "focused" {}
+            }
+        "#
+        ; "marked sections"
+    )]
+    fn lowers_modules(item: &str, describe: &str) {
+        let describe: Describe = syn::parse_str(describe).unwrap();
+
+        assert_eq!(
+            expand("", item).unwrap().to_string(),
+            quote!(mod tests { #describe }).to_string()
+        );
+    }
+
+    #[test]
+    fn lowers_functions() {
+        let item = r#"
+            #[timeout(2s)]
+            fn stack() -> Result<(), Error> {
+                let mut stack = Stack::new();
+
+                when!("an item is pushed", {
+                    stack.push(1)?;
+
+                    then!("it can be popped", {
+                        assert_eq!(stack.pop()?, 1);
+                    });
+                });
+            }
+        "#;
+
+        let section: Section = syn::parse_str(
+            r#"
+                #[timeout(2s)]
+                section "stack" -> Result<(), Error> {
+                    let mut stack = Stack::new();
+
+                    when "an item is pushed" {
+                        stack.push(1)?;
+
+                        then "it can be popped" {
+                            assert_eq!(stack.pop()?, 1);
+                        }
+                    }
+                }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            expand("", item).unwrap().to_string(),
+            section.to_token_stream().to_string()
+        );
+    }
+
+    #[test_case("" => CatchrMode::Regular ; "regular")]
+    #[test_case("tokio" => CatchrMode::Tokio(TokioArgs::default()) ; "tokio")]
+    #[test_case(
+        r#"tokio(flavor = "multi_thread")"#
+            => CatchrMode::Tokio(
+                syn::parse_str(r#"flavor = "multi_thread""#).unwrap()
+            )
+        ; "tokio with args"
+    )]
+    #[test_case("async_std" => CatchrMode::AsyncStd ; "async std")]
+    #[test_case("smol" => CatchrMode::Smol ; "smol")]
+    fn parse_mode(args: &str) -> CatchrMode {
+        syn::parse_str::<Spec>(args).unwrap().mode().clone()
+    }
+
+    #[test_case(
+        "glommio", "mod tests {}"
+            => "expected `tokio`, `async_std` or `smol`"
+        ; "unknown runtime"
+    )]
+    #[test_case(
+        "", "struct Tests;"
+            => "`#[spec]` can only be put on a module or a function"
+        ; "struct item"
+    )]
+    #[test_case(
+        "", "mod tests;" => "`#[spec]` modules must be declared inline"
+        ; "external module"
+    )]
+    #[test_case(
+        "", "fn tests(x: u32) {}" => "`#[spec]` functions can't take arguments"
+        ; "arguments"
+    )]
+    fn errors(args: &str, item: &str) -> String {
+        expand(args, item).unwrap_err().to_string()
+    }
+}
@@ -0,0 +1,93 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::parse::{self, ParseStream, Parser};
+use syn::Token;
+
+/// An attribute added to every test carrying a tag, set at the top of a
+/// `describe!` block, e.g.
+///
+/// ```text
+/// #![tag(slow, cfg_attr(not(feature = "slow-tests"), ignore))]
+/// #![tag(db, cfg(feature = "db"))]
+/// ```
+///
+/// runs the tests tagged as `slow` only when the `slow-tests` feature is
+/// enabled, and doesn't generate the ones tagged as `db` at all without the
+/// `db` feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagRule {
+    tag: String,
+    attr: syn::Attribute,
+}
+
+impl TagRule {
+    pub fn new(tag: impl ToString, attr: syn::Attribute) -> Self {
+        Self {
+            tag: tag.to_string(),
+            attr,
+        }
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    pub fn attr(&self) -> &syn::Attribute {
+        &self.attr
+    }
+
+    pub fn is_tag_attr(attr: &syn::Attribute) -> bool {
+        attr.path.is_ident("tag")
+    }
+
+    pub fn from_attr(attr: &syn::Attribute) -> parse::Result<Self> {
+        attr.parse_args_with(|input: ParseStream| {
+            let tag = input.call(syn::Ident::parse_any)?;
+            input.parse::<Token![,]>()?;
+
+            let meta: TokenStream = input.parse()?;
+
+            if meta.is_empty() {
+                return Err(input.error("expected the attribute of the tests"));
+            }
+
+            let attr = syn::Attribute::parse_outer
+                .parse2(quote!(#[#meta]))?
+                .remove(0);
+
+            Ok(Self::new(tag, attr))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test]
+    fn from_attr() {
+        let attr: syn::Attribute = parse_quote! {
+            #![tag(slow, cfg_attr(not(feature = "slow"), ignore))]
+        };
+
+        assert_eq!(
+            TagRule::from_attr(&attr).unwrap(),
+            TagRule::new(
+                "slow",
+                parse_quote!(#[cfg_attr(not(feature = "slow"), ignore)])
+            )
+        );
+    }
+
+    #[test_case(parse_quote!(#![tag(slow)]) ; "no attribute")]
+    #[test_case(parse_quote!(#![tag(slow,)]) ; "empty attribute")]
+    #[test_case(parse_quote!(#![tag]) ; "no arguments")]
+    #[test_case(parse_quote!(#![tag("slow", ignore)]) ; "not a name")]
+    fn from_attr_error(attr: syn::Attribute) {
+        TagRule::from_attr(&attr).unwrap_err();
+    }
+}
@@ -0,0 +1,110 @@
+use syn::ext::IdentExt;
+use syn::parse::{self, Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::Token;
+
+/// The tags of a section, e.g. `section "db tests" [slow, db] { ... }`.
+/// They're inherited by the nested sections, and can be used to add
+/// attributes to the tests carrying them - see `TagRule`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tags {
+    names: Vec<String>,
+}
+
+impl Tags {
+    pub fn new(names: Vec<String>) -> Self {
+        Self { names }
+    }
+
+    pub fn peek(input: ParseStream) -> bool {
+        input.peek(syn::token::Bracket)
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.iter().any(|own| own == name)
+    }
+
+    pub fn extend(&mut self, other: &Tags) {
+        for name in &other.names {
+            if !self.contains(name) {
+                self.names.push(name.clone());
+            }
+        }
+    }
+
+    /// Splits the tags written at the end of a description, the way Catch2
+    /// does it, e.g. `"db tests [slow][db]"`, from the rest of it.
+    pub fn from_description(description: &str) -> (String, Self) {
+        let mut rest = description.trim_end();
+        let mut names = vec![];
+
+        while let Some(start) =
+            rest.strip_suffix(']').and_then(|s| s.rfind('['))
+        {
+            let name = &rest[start + 1..rest.len() - 1];
+
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                break;
+            }
+
+            names.push(name.to_string());
+            rest = rest[..start].trim_end();
+        }
+
+        if names.is_empty() {
+            return (description.to_string(), Self::default());
+        }
+
+        names.reverse();
+
+        (rest.to_string(), Self::new(names))
+    }
+}
+
+impl Parse for Tags {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let content;
+        syn::bracketed!(content in input);
+
+        let names: Punctuated<syn::Ident, Token![,]> =
+            content.parse_terminated(syn::Ident::parse_any)?;
+
+        Ok(Self::new(names.iter().map(ToString::to_string).collect()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case("[slow, db]" => vec!["slow", "db"] ; "list")]
+    #[test_case("[slow,]" => vec!["slow"] ; "trailing comma")]
+    #[test_case("[async]" => vec!["async"] ; "keyword")]
+    #[test_case("[]" => Vec::<String>::new() ; "empty")]
+    fn parse(s: &str) -> Vec<String> {
+        syn::parse_str::<Tags>(s).unwrap().names().to_vec()
+    }
+
+    #[test_case("db tests [slow][db]", "db tests", &["slow", "db"] ; "tags")]
+    #[test_case("db tests [slow] [db] ", "db tests", &["slow", "db"] ; "spaces")]
+    #[test_case("db tests", "db tests", &[] ; "no tags")]
+    #[test_case("[db] tests", "[db] tests", &[] ; "not at the end")]
+    #[test_case("a [two words]", "a [two words]", &[] ; "whitespace")]
+    #[test_case("empty []", "empty []", &[] ; "empty tag")]
+    fn from_description(s: &str, rest: &str, names: &[&str]) {
+        let (own_rest, tags) = Tags::from_description(s);
+
+        assert_eq!(own_rest, rest);
+        assert_eq!(tags.names(), names);
+    }
+}
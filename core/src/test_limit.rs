@@ -0,0 +1,221 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use syn::parse;
+
+use crate::section::Section;
+use crate::section_item::SectionItem;
+
+/// The number of tests a `describe!` block can generate before a warning is
+/// raised, since a deeply nested tree quietly produces hundreds of them. It
+/// can be changed with `#![max_tests = 500]` at the top of the block - see
+/// `Describe::is_config_attr`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TestLimit {
+    max: usize,
+}
+
+impl Default for TestLimit {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl TestLimit {
+    pub fn new(max: usize) -> Self {
+        Self { max }
+    }
+
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    pub fn is_limit_attr(attr: &syn::Attribute) -> bool {
+        attr.path.is_ident("max_tests")
+    }
+
+    pub fn from_attr(attr: &syn::Attribute) -> parse::Result<Self> {
+        match attr.parse_meta()? {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                lit: syn::Lit::Int(max),
+                ..
+            }) => Ok(Self::new(max.base10_parse()?)),
+            meta => Err(parse::Error::new_spanned(
+                meta,
+                "expected the number of tests, like `#![max_tests = 500]`",
+            )),
+        }
+    }
+
+    /// Raises a warning (through the use of a deprecated constant, as proc
+    /// macros can't emit warnings on stable) if the sections generate more
+    /// tests than the limit, pointing at the subtree generating most of them.
+    pub fn quote_check(&self, sections: &[Section]) -> TokenStream {
+        let total: usize = sections.iter().map(Section::count_tests).sum();
+
+        let largest = match sections.iter().max_by_key(|s| s.count_tests()) {
+            Some(largest) if total > self.max => largest,
+            _ => return TokenStream::new(),
+        };
+
+        let (subtree, path, count) = Self::largest_subtree(largest);
+
+        let message = format!(
+            "this block generates {} tests, more than the limit of {} - {} of \
+             them come from `{}`. Consider `#[runtime_sections]` or \
+             `#[shared_setup]`, or raise the limit with `#![max_tests = ...]` \
+             at the top of the block",
+            total, self.max, count, path,
+        );
+
+        let warning = Ident::new("TOO_MANY_TESTS", subtree.keyword_span());
+
+        quote! {
+            const _: () = {
+                #[deprecated(note = #message)]
+                const #warning: () = ();
+                #warning
+            };
+        }
+    }
+
+    /// Follows the nested sections generating more than half of the tests of
+    /// their parent, returning the last one, its path and the number of tests
+    /// it generates.
+    fn largest_subtree(section: &Section) -> (&Section, String, usize) {
+        let mut path = vec![section.title()];
+        let mut count = section.count_tests();
+        let mut section = section;
+
+        // Every row of the examples of a section or of its ancestors repeats
+        // the tests of its nested sections
+        let mut rows = 1;
+
+        // The nested sections of runtime sections are all in a single test
+        while !section.is_runtime() {
+            rows *= section.examples().map_or(1, |ex| ex.rows().len());
+
+            let largest = section
+                .body()
+                .items()
+                .iter()
+                .filter_map(|item| match item {
+                    SectionItem::Sep(section) => Some(section),
+                    _ => None,
+                })
+                .max_by_key(|section| section.count_tests());
+
+            match largest {
+                Some(largest) if largest.count_tests() * rows * 2 > count => {
+                    path.push(largest.title());
+                    count = largest.count_tests() * rows;
+                    section = largest;
+                }
+                _ => break,
+            }
+        }
+
+        (section, path.join(" / "), count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    fn sections(s: &str) -> Vec<Section> {
+        let describe: crate::Describe = syn::parse_str(s).unwrap();
+
+        describe.sections().to_vec()
+    }
+
+    #[test]
+    fn under_limit() {
+        let sections = sections(
+            r#"
+                when "a" {
+                    then "b" {}
+                    then "c" {}
+                }
+            "#,
+        );
+
+        assert!(TestLimit::new(2).quote_check(&sections).is_empty());
+    }
+
+    #[test_case(
+        r#"
+            when "a" {
+                then "b" with [1, 2, 3] as x {}
+                then "c" {}
+            }
+            when "d" {}
+        "#
+        => "this block generates 5 tests, more than the limit of 2 - 3 of \
+            them come from `when \"a\" / then \"b\"`"
+        ; "nested"
+    )]
+    #[test_case(
+        r#"
+            when "a" with [1, 2] as x {
+                then "b" {}
+                then "c" {}
+            }
+        "#
+        => "this block generates 4 tests, more than the limit of 2 - 4 of \
+            them come from `when \"a\"`"
+        ; "evenly split"
+    )]
+    #[test_case(
+        r#"
+            when "a" with [1, 2] as x {
+                when "b" {
+                    then "c" with [1, 2, 3, 4] as y {}
+                }
+            }
+        "#
+        => "this block generates 8 tests, more than the limit of 2 - 8 of \
+            them come from `when \"a\" / when \"b\" / then \"c\"`"
+        ; "examples on ancestors"
+    )]
+    #[test_case(
+        r#"
+            #[runtime_sections]
+            section "a" with [1, 2, 3] as x {
+                then "b" {}
+                then "c" {}
+            }
+        "#
+        => "this block generates 3 tests, more than the limit of 2 - 3 of \
+            them come from `section \"a\"`"
+        ; "runtime sections"
+    )]
+    fn over_limit(s: &str) -> String {
+        let tokens = TestLimit::new(2).quote_check(&sections(s));
+        let check: syn::ItemConst = syn::parse2(tokens).unwrap();
+
+        let message = match &*check.expr {
+            syn::Expr::Block(block) => match &block.block.stmts[0] {
+                syn::Stmt::Item(syn::Item::Const(warning)) => {
+                    match warning.attrs[0].parse_meta().unwrap() {
+                        syn::Meta::List(list) => match &list.nested[0] {
+                            syn::NestedMeta::Meta(syn::Meta::NameValue(
+                                syn::MetaNameValue {
+                                    lit: syn::Lit::Str(note),
+                                    ..
+                                },
+                            )) => note.value(),
+                            _ => panic!("not a note"),
+                        },
+                        _ => panic!("not deprecated"),
+                    }
+                }
+                _ => panic!("not a const"),
+            },
+            _ => panic!("not a block"),
+        };
+
+        message.split(". ").next().unwrap().to_string()
+    }
+}
@@ -0,0 +1,180 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::parse::{self, Parse, ParseStream};
+
+/// A time limit of the tests in a section, given with an attribute like
+/// `#[timeout(2s)]` (or `500ms`, `1m`).
+#[derive(Debug, Clone)]
+pub struct Timeout {
+    millis: u64,
+    span: Span,
+}
+
+impl Timeout {
+    pub fn new(millis: u64) -> Self {
+        Self {
+            millis,
+            span: Span::call_site(),
+        }
+    }
+
+    pub fn millis(&self) -> u64 {
+        self.millis
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn is_timeout_attr(attr: &syn::Attribute) -> bool {
+        attr.path.is_ident("timeout")
+    }
+
+    pub fn from_attr(attr: &syn::Attribute) -> parse::Result<Self> {
+        attr.parse_args()
+    }
+
+    /// The limit in the units it was given in, for failure messages.
+    fn describe(&self) -> String {
+        match self.millis {
+            millis if millis % 60_000 == 0 => format!("{}m", millis / 60_000),
+            millis if millis % 1000 == 0 => format!("{}s", millis / 1000),
+            millis => format!("{}ms", millis),
+        }
+    }
+
+    fn message(&self, path: &str) -> String {
+        format!("{} timed out after {}", path, self.describe())
+    }
+
+    fn quote_duration(&self) -> TokenStream {
+        let millis = self.millis;
+
+        quote!(::std::time::Duration::from_millis(#millis))
+    }
+
+    /// Runs the body of a sync test on a separate thread, failing the test if
+    /// it doesn't finish in time.
+    pub fn quote_watchdog(&self, body: TokenStream, path: &str) -> TokenStream {
+        let duration = self.quote_duration();
+        let message = self.message(path);
+
+        quote! {
+            ::catchr::__private::run_with_timeout(#duration, #message, move || {
+                #body
+            })
+        }
+    }
+
+    /// Awaits the body of a test with `timeout`, a function with the
+    /// signature of `tokio::time::timeout`.
+    pub fn quote_future(
+        &self,
+        timeout: TokenStream,
+        body: TokenStream,
+        path: &str,
+    ) -> TokenStream {
+        let duration = self.quote_duration();
+        let message = self.message(path);
+
+        quote! {
+            match #timeout(#duration, async { #body }).await {
+                ::std::result::Result::Ok(output) => output,
+                ::std::result::Result::Err(_) => panic!("{}", #message),
+            }
+        }
+    }
+}
+
+// Spans are left out, like in sections.
+impl PartialEq for Timeout {
+    fn eq(&self, other: &Self) -> bool {
+        self.millis == other.millis
+    }
+}
+
+impl Eq for Timeout {}
+
+impl Parse for Timeout {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let lit: syn::LitInt = input.parse().map_err(|err| {
+            parse::Error::new(
+                err.span(),
+                "expected a duration, like `500ms`, `2s` or `1m`",
+            )
+        })?;
+
+        let unit = match lit.suffix() {
+            "ms" => 1,
+            "s" => 1000,
+            "m" => 60_000,
+            _ => {
+                return Err(parse::Error::new(
+                    lit.span(),
+                    "expected a duration, like `500ms`, `2s` or `1m`",
+                ))
+            }
+        };
+
+        let millis =
+            lit.base10_parse::<u64>()?
+                .checked_mul(unit)
+                .ok_or_else(|| {
+                    parse::Error::new(lit.span(), "the timeout is too long")
+                })?;
+
+        Ok(Self {
+            millis,
+            span: lit.span(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case("500ms" => 500     ; "millis")]
+    #[test_case("2s"    => 2000    ; "seconds")]
+    #[test_case("1m"    => 60_000  ; "minutes")]
+    fn parse(s: &str) -> u64 {
+        syn::parse_str::<Timeout>(s).unwrap().millis()
+    }
+
+    #[test_case("2"     ; "no unit")]
+    #[test_case("2h"    ; "unknown unit")]
+    #[test_case("1.5s"  ; "fraction")]
+    #[test_case("\"2s\"" ; "string")]
+    fn rejects_invalid_durations(s: &str) {
+        let err = syn::parse_str::<Timeout>(s).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "expected a duration, like `500ms`, `2s` or `1m`"
+        );
+    }
+
+    #[test_case(
+        "18446744073709551615s" => "the timeout is too long" ; "seconds"
+    )]
+    #[test_case(
+        "18446744073709551615m" => "the timeout is too long" ; "minutes"
+    )]
+    #[test_case(
+        "18446744073709551616ms" => "number too large to fit in target type"
+        ; "millis"
+    )]
+    fn rejects_too_long_durations(s: &str) -> String {
+        syn::parse_str::<Timeout>(s).unwrap_err().to_string()
+    }
+
+    #[test_case(500     => "500ms" ; "millis")]
+    #[test_case(2000    => "2s"    ; "seconds")]
+    #[test_case(120_000 => "2m"    ; "minutes")]
+    #[test_case(1500    => "1500ms" ; "mixed")]
+    fn describe(millis: u64) -> String {
+        Timeout::new(millis).describe()
+    }
+}
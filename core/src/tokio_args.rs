@@ -0,0 +1,173 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::{self, Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::Token;
+
+/// Configuration of the tokio runtime, passed on to `#[tokio::test]`, e.g.
+/// `flavor = "multi_thread", worker_threads = 4`.
+///
+/// It's given in the header of `describe_tokio!` and can be overridden for
+/// a subtree with a `#[tokio(...)]` attribute on a section.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokioArgs {
+    args: Vec<syn::MetaNameValue>,
+}
+
+impl TokioArgs {
+    pub fn new(args: Vec<syn::MetaNameValue>) -> Self {
+        Self { args }
+    }
+
+    pub fn args(&self) -> &[syn::MetaNameValue] {
+        &self.args
+    }
+
+    pub fn is_tokio_attr(attr: &syn::Attribute) -> bool {
+        attr.path.is_ident("tokio")
+    }
+
+    pub fn from_attr(attr: &syn::Attribute) -> parse::Result<Self> {
+        attr.parse_args()
+    }
+
+    /// Parses the optional header of `describe_tokio!` - the arguments,
+    /// followed by a `;`.
+    pub fn parse_header(input: ParseStream) -> parse::Result<Self> {
+        if !(input.peek(syn::Ident) && input.peek2(Token![=])) {
+            return Ok(Self::default());
+        }
+
+        let mut args = vec![];
+
+        loop {
+            args.push(input.parse()?);
+
+            if input.peek(Token![;]) {
+                input.parse::<Token![;]>()?;
+                break;
+            }
+
+            input.parse::<Token![,]>()?;
+
+            if input.peek(Token![;]) {
+                input.parse::<Token![;]>()?;
+                break;
+            }
+        }
+
+        Ok(Self::new(args))
+    }
+
+    fn sets(&self, name: &str) -> bool {
+        self.args.iter().any(|arg| arg.path.is_ident(name))
+    }
+
+    /// Arguments of `self`, with the ones given in `other` taking precedence.
+    /// Since `worker_threads` only makes sense for the multi-threaded
+    /// runtime, it isn't inherited by sections choosing their own flavor.
+    pub fn merge(&self, other: &Self) -> Self {
+        let mut args: Vec<_> = self
+            .args
+            .iter()
+            .filter(|arg| !other.args.iter().any(|own| own.path == arg.path))
+            .filter(|arg| {
+                !(other.sets("flavor") && arg.path.is_ident("worker_threads"))
+            })
+            .cloned()
+            .collect();
+
+        args.extend(other.args.iter().cloned());
+
+        Self::new(args)
+    }
+
+    pub fn quote_attr(&self) -> TokenStream {
+        let args = &self.args;
+
+        if args.is_empty() {
+            quote!(#[tokio::test])
+        } else {
+            quote!(#[tokio::test(#(#args),*)])
+        }
+    }
+}
+
+impl Parse for TokioArgs {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let args =
+            Punctuated::<syn::MetaNameValue, Token![,]>::parse_terminated(
+                input,
+            )?;
+
+        Ok(Self::new(args.into_iter().collect()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse::Parser;
+
+    use super::*;
+
+    fn parse_header(s: &str) -> (TokioArgs, TokenStream) {
+        let parser = |input: ParseStream| {
+            let args = TokioArgs::parse_header(input)?;
+            let rest: TokenStream = input.parse()?;
+
+            Ok((args, rest))
+        };
+
+        parser.parse_str(s).unwrap()
+    }
+
+    #[test]
+    fn header() {
+        let (args, rest) = parse_header(
+            r#"flavor = "multi_thread", worker_threads = 4; when "x" {}"#,
+        );
+
+        assert_eq!(
+            args.quote_attr().to_string(),
+            quote!(#[tokio::test(flavor = "multi_thread", worker_threads = 4)])
+                .to_string()
+        );
+        assert_eq!(rest.to_string(), quote!(when "x" {}).to_string());
+    }
+
+    #[test]
+    fn missing_header() {
+        let (args, rest) = parse_header(r#"when "x" {}"#);
+
+        assert_eq!(args.quote_attr().to_string(), "# [tokio :: test]");
+        assert_eq!(rest.to_string(), quote!(when "x" {}).to_string());
+    }
+
+    #[test]
+    fn merge() {
+        let args: TokioArgs =
+            syn::parse_str(r#"flavor = "multi_thread", worker_threads = 4"#)
+                .unwrap();
+        let own: TokioArgs =
+            syn::parse_str("worker_threads = 1, start_paused = true").unwrap();
+
+        assert_eq!(
+            args.merge(&own),
+            syn::parse_str(
+                r#"flavor = "multi_thread", worker_threads = 1, start_paused = true"#
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn merge_flavor() {
+        let args: TokioArgs =
+            syn::parse_str(r#"flavor = "multi_thread", worker_threads = 4"#)
+                .unwrap();
+        let own: TokioArgs =
+            syn::parse_str(r#"flavor = "current_thread""#).unwrap();
+
+        assert_eq!(args.merge(&own), own);
+    }
+}
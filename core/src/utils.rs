@@ -1,5 +1,10 @@
 use itertools::Itertools;
+use proc_macro2::{Delimiter, Span, TokenStream, TokenTree};
 
+/// Turns a section description into something usable as (a part of) an
+/// identifier. Characters allowed in Rust identifiers are kept, including
+/// non-ASCII ones, so that e.g. `"zamówienie"` doesn't turn into
+/// `"zam_wienie"` - everything else is replaced with underscores.
 pub fn escape_name(input: impl AsRef<str>) -> String {
     if input.as_ref().is_empty() {
         return "empty".to_string();
@@ -7,10 +12,10 @@ pub fn escape_name(input: impl AsRef<str>) -> String {
 
     let s: String = input
         .as_ref()
-        .to_ascii_lowercase()
+        .to_lowercase()
         .chars()
         .map(|c| match c {
-            c if c.is_ascii_alphabetic() || c.is_numeric() => c,
+            c if unicode_ident::is_xid_continue(c) => c,
             _ => '_',
         })
         .dedup_by(|a, b| *a == '_' && a == b)
@@ -19,15 +24,54 @@ pub fn escape_name(input: impl AsRef<str>) -> String {
     s.trim_end_matches('_').to_string()
 }
 
-pub fn extract_literal_string(lit: syn::Lit) -> Option<String> {
-    match lit {
-        syn::Lit::Str(s) => Some(s.value()),
-        _ => None,
+/// Looks for an `.await` outside of async blocks (or async fns and
+/// closures), returning its span.
+pub fn find_await(tokens: TokenStream) -> Option<Span> {
+    let tokens: Vec<_> = tokens.into_iter().collect();
+
+    for (idx, token) in tokens.iter().enumerate() {
+        match token {
+            TokenTree::Ident(ident) if ident == "await" => {
+                if let Some(TokenTree::Punct(punct)) =
+                    idx.checked_sub(1).map(|idx| &tokens[idx])
+                {
+                    if punct.as_char() == '.' {
+                        return Some(ident.span());
+                    }
+                }
+            }
+            TokenTree::Group(group) if !is_async_body(&tokens[..idx]) => {
+                if let Some(span) = find_await(group.stream()) {
+                    return Some(span);
+                }
+            }
+            _ => {}
+        }
     }
+
+    None
+}
+
+/// Checks whether a group following `preceding` is the body of an async
+/// block, fn or closure - that is whether there's an `async` since the end of
+/// the last statement.
+fn is_async_body(preceding: &[TokenTree]) -> bool {
+    preceding
+        .iter()
+        .rev()
+        .take_while(|token| match token {
+            TokenTree::Punct(punct) => punct.as_char() != ';',
+            TokenTree::Group(group) => group.delimiter() != Delimiter::Brace,
+            _ => true,
+        })
+        .any(|token| matches!(token, TokenTree::Ident(ident) if ident == "async"))
 }
 
 #[cfg(test)]
 mod tests {
+    use proc_macro2::Ident;
+    use test_case::test_case;
+
     use super::*;
 
     #[test]
@@ -43,4 +87,38 @@ mod tests {
         );
         assert_eq!("empty", &escape_name(""))
     }
+
+    #[test_case("Złóż zamówienie"       => "złóż_zamówienie"    ; "polish")]
+    #[test_case("Größe ändern"          => "größe_ändern"       ; "german")]
+    #[test_case("注文を確定する"          => "注文を確定する"       ; "japanese")]
+    #[test_case("Ввод пароля"           => "ввод_пароля"        ; "cyrillic")]
+    #[test_case("Λάθος κωδικός!"        => "λάθος_κωδικός"      ; "greek")]
+    #[test_case("उपयोगकर्ता जोड़ें"          => "उपयोगकर्ता_जोड़ें"      ; "devanagari")]
+    #[test_case("مرحبا بالعالم"          => "مرحبا_بالعالم"       ; "arabic")]
+    #[test_case("x² is ½ of 🎉 y"        => "x_is_of_y"          ; "non identifier characters")]
+    fn escapes_unicode_strings(s: &str) -> String {
+        escape_name(s)
+    }
+
+    #[test_case("Złóż zamówienie"   ; "polish")]
+    #[test_case("Größe ändern"      ; "german")]
+    #[test_case("注文を確定する"      ; "japanese")]
+    #[test_case("Ввод пароля"       ; "cyrillic")]
+    #[test_case("उपयोगकर्ता जोड़ें"      ; "devanagari")]
+    #[test_case("x² is ½ of 🎉 y"    ; "non identifier characters")]
+    fn escaped_unicode_strings_are_identifiers(s: &str) {
+        Ident::new(&format!("then_{}", escape_name(s)), Span::call_site());
+    }
+
+    #[test_case("let x = fetch().await;"                     => true  ; "plain await")]
+    #[test_case("if x { let y = fetch().await; }"            => true  ; "nested await")]
+    #[test_case("let x = fetch();"                           => false ; "no await")]
+    #[test_case("let await_x = 1; x.awaited();"              => false ; "await in names")]
+    #[test_case("block_on(async { fetch().await });"         => false ; "async block")]
+    #[test_case("block_on(async move { fetch().await });"    => false ; "async move block")]
+    #[test_case("async fn f() { fetch().await }"             => false ; "async fn")]
+    #[test_case("let f = async { 1 }; { f.await }"           => true  ; "after async block")]
+    fn finds_await(s: &str) -> bool {
+        find_await(s.parse().unwrap()).is_some()
+    }
 }
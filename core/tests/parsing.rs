@@ -1,4 +1,22 @@
-use catchr_core::{Section, SectionBody, SectionItem, SectionKeyword};
+use catchr_core::{
+    Defer, Describe, Examples, Returns, Section, SectionBody, SectionItem,
+    SectionKeyword, Tags, TestLimit,
+};
+
+fn parse_error_location<T: syn::parse::Parse + std::fmt::Debug>(
+    s: &str,
+) -> (usize, usize) {
+    parse_error::<T>(s).1
+}
+
+fn parse_error<T: syn::parse::Parse + std::fmt::Debug>(
+    s: &str,
+) -> (String, (usize, usize)) {
+    let err = syn::parse_str::<T>(s).unwrap_err();
+    let start = err.span().start();
+
+    (err.to_string(), (start.line, start.column))
+}
 
 fn syn_parse<T: syn::parse::Parse>(s: impl AsRef<str>) -> T {
     syn::parse_str(s.as_ref()).unwrap()
@@ -52,4 +70,478 @@ fn nested_one() {
     );
 }
 
+#[test]
+fn describe_with_multiple_roots() {
+    let raw = r#"
+        fn helper() -> bool {
+            true
+        }
+
+        when "Hello!" {
+
+        }
+
+        then "Whatever" {
+            assert!(helper());
+        }
+    "#;
+
+    let describe = syn::parse_str::<Describe>(raw).unwrap();
+
+    assert_eq!(
+        describe,
+        Describe::new(
+            vec![syn_parse("fn helper() -> bool { true }")],
+            vec![
+                Section::new(
+                    SectionKeyword::When,
+                    "Hello!".to_string(),
+                    SectionBody::empty(),
+                ),
+                Section::new(
+                    SectionKeyword::Then,
+                    "Whatever".to_string(),
+                    SectionBody::new(vec![SectionItem::Stmt(syn_parse(
+                        "assert!(helper());"
+                    ))]),
+                ),
+            ],
+        )
+    );
+}
+
+#[test]
+fn describe_with_test_limit() {
+    let raw = r#"
+        #![max_tests = 1000]
+
+        when "Hello!" {
+
+        }
+    "#;
+
+    let describe = syn::parse_str::<Describe>(raw).unwrap();
+
+    assert_eq!(
+        describe,
+        Describe::new(
+            vec![],
+            vec![Section::new(
+                SectionKeyword::When,
+                "Hello!".to_string(),
+                SectionBody::empty(),
+            )],
+        )
+        .with_limit(TestLimit::new(1000))
+    );
+}
+
+#[test]
+fn section_with_tags() {
+    let raw = r#"
+        section "db tests [slow]" [db, slow] {
+            then "Whatever" [fast] {}
+        }
+    "#;
+
+    let section = syn::parse_str::<Section>(raw).unwrap();
+
+    assert_eq!(
+        section,
+        Section::new(
+            SectionKeyword::Section,
+            "db tests".to_string(),
+            SectionBody::new(vec![SectionItem::Sep(
+                Section::new(
+                    SectionKeyword::Then,
+                    "Whatever".to_string(),
+                    SectionBody::empty(),
+                )
+                .with_tags(Tags::new(vec!["fast".to_string()])),
+            )]),
+        )
+        .with_tags(Tags::new(vec!["slow".to_string(), "db".to_string()]))
+    );
+}
+
+#[test]
+fn section_with_attributes() {
+    let raw = r#"
+        #[ignore]
+        when "Hello!" {
+            #[should_panic(expected = "boom")]
+            then "Whatever" {
+                panic!("boom");
+            }
+        }
+    "#;
+
+    let section = syn::parse_str::<Section>(raw).unwrap();
+
+    assert_eq!(
+        section,
+        Section::new(
+            SectionKeyword::When,
+            "Hello!".to_string(),
+            SectionBody::new(vec![SectionItem::Sep(
+                Section::new(
+                    SectionKeyword::Then,
+                    "Whatever".to_string(),
+                    SectionBody::new(vec![SectionItem::Stmt(syn_parse(
+                        "panic!(\"boom\");"
+                    ))]),
+                )
+                .with_attrs(vec![
+                    syn::parse_quote!(#[should_panic(expected = "boom")])
+                ]),
+            )]),
+        )
+        .with_attrs(vec![syn::parse_quote!(#[ignore])])
+    );
+}
+
+#[test]
+fn invalid_literal_error_points_at_literal() {
+    let raw = r#"
+        when "Hello!" {
+            then 42 {
+            }
+        }
+    "#;
+
+    assert_eq!(
+        parse_error::<Describe>(raw),
+        ("Invalid section literal".to_string(), (3, 17))
+    );
+}
+
+#[test]
+fn missing_brace_error_points_at_token() {
+    let raw = r#"
+        when "Hello!" {
+            then "Whatever" assert!(true);
+        }
+    "#;
+
+    assert_eq!(
+        parse_error::<Describe>(raw),
+        ("expected curly braces".to_string(), (3, 28))
+    );
+}
+
+#[test]
+fn misused_generator_error_points_at_macro() {
+    let raw = r#"
+        then "Whatever" {
+            assert!(check(generate!(1, 2)));
+        }
+    "#;
+
+    assert_eq!(parse_error_location::<Section>(raw), (3, 26));
+}
+
+#[test]
+fn invalid_keyword_error_points_at_keyword() {
+    let err = syn::parse_str::<SectionKeyword>("  whence").unwrap_err();
+    let start = err.span().start();
+
+    assert_eq!((start.line, start.column), (1, 2));
+}
+
+#[test]
+fn keeps_spans_of_keyword_and_description() {
+    let raw = r#"
+        when "Hello!" {
+        }
+    "#;
+
+    let section = syn::parse_str::<Section>(raw).unwrap();
+
+    let keyword = section.keyword_span().start();
+    let name = section.name_span().start();
+
+    assert_eq!((keyword.line, keyword.column), (2, 8));
+    assert_eq!((name.line, name.column), (2, 13));
+}
+
+#[test]
+fn keywords_used_as_identifiers() {
+    let raw = r#"
+        when "Hello!" {
+            let mut case = Case::default();
+            case.run();
+            section(1);
+            when = 3;
+            let given = then(when);
+        }
+    "#;
+
+    let section = syn::parse_str::<Section>(raw).unwrap();
+
+    assert_eq!(
+        section,
+        Section::new(
+            SectionKeyword::When,
+            "Hello!".to_string(),
+            SectionBody::new(vec![
+                SectionItem::Stmt(syn_parse("let mut case = Case::default();")),
+                SectionItem::Stmt(syn_parse("case.run();")),
+                SectionItem::Stmt(syn_parse("section(1);")),
+                SectionItem::Stmt(syn_parse("when = 3;")),
+                SectionItem::Stmt(syn_parse("let given = then(when);")),
+            ]),
+        )
+    );
+}
+
+#[test]
+fn section_with_examples() {
+    let raw = r#"
+        case "adds {a} and {b}" with [(1, 2, 3), (4, 5, 9)] as (a, b, expected) {
+            assert_eq!(a + b, expected);
+        }
+    "#;
+
+    let section = syn::parse_str::<Section>(raw).unwrap();
+
+    assert_eq!(
+        section,
+        Section::new(
+            SectionKeyword::Case,
+            "adds {a} and {b}".to_string(),
+            SectionBody::new(vec![SectionItem::Stmt(syn_parse(
+                "assert_eq!(a + b, expected);"
+            ))]),
+        )
+        .with_examples(Examples::new(
+            vec![syn_parse("(1, 2, 3)"), syn_parse("(4, 5, 9)")],
+            syn_parse("(a, b, expected)"),
+        ))
+    );
+}
+
+#[test]
+fn section_with_return_type() {
+    let raw = r#"
+        given "a number" -> Result<(), ParseIntError> {
+            let x: u32 = "1".parse()?;
+        }
+    "#;
+
+    let section = syn::parse_str::<Section>(raw).unwrap();
+
+    assert_eq!(
+        section,
+        Section::new(
+            SectionKeyword::Given,
+            "a number".to_string(),
+            SectionBody::new(vec![SectionItem::Stmt(syn_parse(
+                r#"let x: u32 = "1".parse()?;"#
+            ))]),
+        )
+        .with_returns(Returns::new(syn_parse("Result<(), ParseIntError>")))
+    );
+}
+
+#[test]
+fn section_with_defer() {
+    let raw = r#"
+        given "a server" {
+            let server = start();
+            defer {
+                server.stop();
+            }
+            let defer = 1;
+        }
+    "#;
+
+    let section = syn::parse_str::<Section>(raw).unwrap();
+
+    assert_eq!(
+        section,
+        Section::new(
+            SectionKeyword::Given,
+            "a server".to_string(),
+            SectionBody::new(vec![
+                SectionItem::Stmt(syn_parse("let server = start();")),
+                SectionItem::Defer(Defer::new(syn_parse("{ server.stop(); }"))),
+                SectionItem::Stmt(syn_parse("let defer = 1;")),
+            ]),
+        )
+    );
+}
+
+#[test]
+fn async_section() {
+    let raw = r#"
+        when "Hello!" {
+            async then "Whatever" {
+                fetch().await;
+            }
+        }
+    "#;
+
+    let section = syn::parse_str::<Section>(raw).unwrap();
+
+    assert_eq!(
+        section,
+        Section::new(
+            SectionKeyword::When,
+            "Hello!".to_string(),
+            SectionBody::new(vec![SectionItem::Sep(
+                Section::new(
+                    SectionKeyword::Then,
+                    "Whatever".to_string(),
+                    SectionBody::new(vec![SectionItem::Stmt(syn_parse(
+                        "fetch().await;"
+                    ))]),
+                )
+                .with_async(true)
+            )]),
+        )
+    );
+}
+
+#[test]
+fn section_with_runtime_sections() {
+    let raw = r#"
+        #[runtime_sections]
+        #[ignore]
+        when "Hello!" {
+            then "Whatever" {}
+        }
+    "#;
+
+    let section = syn::parse_str::<Section>(raw).unwrap();
+
+    assert_eq!(
+        section,
+        Section::new(
+            SectionKeyword::When,
+            "Hello!".to_string(),
+            SectionBody::new(vec![SectionItem::Sep(Section::new(
+                SectionKeyword::Then,
+                "Whatever".to_string(),
+                SectionBody::empty(),
+            ))]),
+        )
+        .with_attrs(vec![syn::parse_quote!(#[ignore])])
+        .with_runtime(true)
+    );
+}
+
+#[test]
+fn pending_sections() {
+    let raw = r#"
+        when "Hello!" {
+            xthen "Whatever" {}
+            pending "Later" {}
+        }
+    "#;
+
+    let section = syn::parse_str::<Section>(raw).unwrap();
+
+    assert_eq!(
+        section,
+        Section::new(
+            SectionKeyword::When,
+            "Hello!".to_string(),
+            SectionBody::new(vec![
+                SectionItem::Sep(
+                    Section::new(
+                        SectionKeyword::Then,
+                        "Whatever".to_string(),
+                        SectionBody::empty(),
+                    )
+                    .with_pending(true)
+                ),
+                SectionItem::Sep(
+                    Section::new(
+                        SectionKeyword::Pending,
+                        "Later".to_string(),
+                        SectionBody::empty(),
+                    )
+                    .with_pending(true)
+                ),
+            ]),
+        )
+    );
+}
+
+#[test]
+fn describe_with_pending_if_empty() {
+    let raw = r#"
+        #![pending_if_empty]
+
+        when "Hello!" {}
+    "#;
+
+    let describe = syn::parse_str::<Describe>(raw).unwrap();
+
+    assert_eq!(
+        describe,
+        Describe::new(
+            vec![],
+            vec![Section::new(
+                SectionKeyword::When,
+                "Hello!".to_string(),
+                SectionBody::empty(),
+            )],
+        )
+        .with_pending_if_empty(true)
+    );
+}
+
+#[test]
+fn focused_sections() {
+    let raw = r#"
+        when "Hello!" {
+            fthen "Whatever" {}
+
+            #[focus]
+            then "Other" {}
+        }
+    "#;
+
+    let section = syn::parse_str::<Section>(raw).unwrap();
+
+    assert_eq!(
+        section,
+        Section::new(
+            SectionKeyword::When,
+            "Hello!".to_string(),
+            SectionBody::new(vec![
+                SectionItem::Sep(
+                    Section::new(
+                        SectionKeyword::Then,
+                        "Whatever".to_string(),
+                        SectionBody::empty(),
+                    )
+                    .with_focused(true)
+                ),
+                SectionItem::Sep(
+                    Section::new(
+                        SectionKeyword::Then,
+                        "Other".to_string(),
+                        SectionBody::empty(),
+                    )
+                    .with_focused(true)
+                ),
+            ]),
+        )
+    );
+    assert!(section.has_focus());
+    assert!(!section.is_focused());
+}
+
+#[test]
+fn focus_with_arguments_error_points_at_arguments() {
+    let raw = r#"
+        #[focus(now)]
+        then "Whatever" {}
+    "#;
+
+    assert_eq!(parse_error_location::<Section>(raw), (2, 15));
+}
+
 // TODO: More tests!
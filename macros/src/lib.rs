@@ -0,0 +1,132 @@
+extern crate proc_macro;
+
+use catchr_core::{CatchrMode, Describe, Spec, TokioArgs};
+use proc_macro::TokenStream;
+use quote::ToTokens;
+use syn::parse::{ParseStream, Parser};
+use syn::{parse_macro_input, Token};
+
+#[proc_macro]
+pub fn describe(input: TokenStream) -> TokenStream {
+    let describe = parse_macro_input!(input as Describe);
+
+    let output = describe.to_token_stream();
+
+    output.into()
+}
+
+/// Like `describe!`, but the tests are run by tokio. The runtime can be
+/// configured with the arguments of `#[tokio::test]` given before the
+/// sections, e.g. `describe_tokio!(flavor = "multi_thread"; ...)`.
+#[proc_macro]
+pub fn describe_tokio(input: TokenStream) -> TokenStream {
+    let parser = |input: ParseStream| {
+        let args = TokioArgs::parse_header(input)?;
+        let describe: Describe = input.parse()?;
+
+        Ok(describe.with_mode(CatchrMode::Tokio(args)))
+    };
+
+    let output = match parser.parse(input) {
+        Ok(describe) => describe.to_token_stream(),
+        Err(err) => err.to_compile_error(),
+    };
+
+    output.into()
+}
+
+#[proc_macro]
+pub fn describe_async_std(input: TokenStream) -> TokenStream {
+    let describe = parse_macro_input!(input as Describe);
+
+    let output = describe.with_mode(CatchrMode::AsyncStd).to_token_stream();
+
+    output.into()
+}
+
+#[proc_macro]
+pub fn describe_smol(input: TokenStream) -> TokenStream {
+    let describe = parse_macro_input!(input as Describe);
+
+    let output = describe.with_mode(CatchrMode::Smol).to_token_stream();
+
+    output.into()
+}
+
+/// Like `describe!`, but the tests are async and run with the executor given
+/// before the sections, e.g.
+/// `describe_async!(futures::executor::block_on; section "..." { ... })`.
+#[proc_macro]
+pub fn describe_async(input: TokenStream) -> TokenStream {
+    let parser = |input: ParseStream| {
+        let executor: syn::Path = input.parse()?;
+        input.parse::<Token![;]>()?;
+        let describe: Describe = input.parse()?;
+
+        Ok(describe.with_mode(CatchrMode::Async(executor)))
+    };
+
+    let output = match parser.parse(input) {
+        Ok(describe) => describe.to_token_stream(),
+        Err(err) => err.to_compile_error(),
+    };
+
+    output.into()
+}
+
+/// Like `describe!`, but the tests are marked with the attributes given
+/// before the sections, instead of `#[test]`. With `async` after the
+/// attributes the tests are async fns, e.g.
+/// `describe_with!(#[sqlx::test], async; section "..." { ... })`.
+#[proc_macro]
+pub fn describe_with(input: TokenStream) -> TokenStream {
+    let parser = |input: ParseStream| {
+        let span = input.span();
+        let attrs = input.call(syn::Attribute::parse_outer)?;
+
+        if attrs.is_empty() {
+            return Err(syn::Error::new(
+                span,
+                "expected test attributes, e.g. `#[test_log::test]`",
+            ));
+        }
+
+        let is_async = if input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+            input.parse::<Token![async]>()?;
+            true
+        } else {
+            false
+        };
+
+        input.parse::<Token![;]>()?;
+        let describe: Describe = input.parse()?;
+
+        Ok(describe.with_mode(CatchrMode::Custom { attrs, is_async }))
+    };
+
+    let output = match parser.parse(input) {
+        Ok(describe) => describe.to_token_stream(),
+        Err(err) => err.to_compile_error(),
+    };
+
+    output.into()
+}
+
+/// The attribute form of `describe!`, put on a module or a function whose
+/// sections are written as macro calls, e.g. `when!("x", { ... })`. The
+/// runtime is given in the arguments - `#[spec(tokio)]`,
+/// `#[spec(tokio(flavor = "multi_thread"))]`, `#[spec(async_std)]` or
+/// `#[spec(smol)]`.
+#[proc_macro_attribute]
+pub fn spec(args: TokenStream, input: TokenStream) -> TokenStream {
+    let spec = parse_macro_input!(args as Spec);
+    let item = parse_macro_input!(input as syn::Item);
+
+    let output = match spec.expand(item) {
+        Ok(output) => output,
+        Err(err) => err.to_compile_error(),
+    };
+
+    output.into()
+}